@@ -0,0 +1,89 @@
+use crate::tsv::{escape_field, unescape_field};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File name of the annotations store inside the rsdu data directory.
+const STORE_FILE: &str = "annotations.tsv";
+
+/// User notes attached to paths, persisted across sessions.
+///
+/// The store is a tab-separated file of `path<TAB>note` lines in the XDG data directory
+/// (`~/.local/share/rsdu/annotations.tsv` on Linux). Tabs, newlines and backslashes in
+/// either column are escaped.
+#[derive(Debug, Default)]
+pub struct Annotations {
+    notes: HashMap<PathBuf, String>,
+    store: Option<PathBuf>,
+}
+
+impl Annotations {
+    /// Loads the annotations store from the default location.
+    ///
+    /// A missing store is not an error and yields an empty set of annotations.
+    pub fn load() -> io::Result<Self> {
+        let store = dirs::data_dir().map(|dir| dir.join("rsdu").join(STORE_FILE));
+        let notes = match &store {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(contents) => parse_store(&contents),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+                Err(e) => return Err(e),
+            },
+            None => HashMap::new(),
+        };
+        Ok(Self { notes, store })
+    }
+
+    /// Returns the note attached to `path`, if any.
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        self.notes.get(path).map(String::as_str)
+    }
+
+    /// Attaches `note` to `path`, or removes the annotation when `note` is blank, and saves.
+    pub fn set(&mut self, path: &Path, note: &str) -> io::Result<()> {
+        let note = note.trim();
+        if note.is_empty() {
+            self.notes.remove(path);
+        } else {
+            self.notes.insert(path.to_path_buf(), note.to_string());
+        }
+        self.save()
+    }
+
+    /// Writes all annotations back to the store.
+    fn save(&self) -> io::Result<()> {
+        let Some(store) = &self.store else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no data directory available to store annotations",
+            ));
+        };
+        if let Some(parent) = store.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut lines: Vec<String> = self
+            .notes
+            .iter()
+            .map(|(path, note)| {
+                format!(
+                    "{}\t{}\n",
+                    escape_field(&path.to_string_lossy()),
+                    escape_field(note)
+                )
+            })
+            .collect();
+        lines.sort();
+        fs::write(store, lines.concat())
+    }
+}
+
+/// Parses the contents of the store, skipping malformed lines.
+fn parse_store(contents: &str) -> HashMap<PathBuf, String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(path, note)| (PathBuf::from(unescape_field(path)), unescape_field(note)))
+        .collect()
+}
@@ -0,0 +1,810 @@
+use crate::annotations::Annotations;
+use crate::format::SizeStyle;
+use librsdu::duplicates::{find_duplicates, Duplicates};
+use librsdu::mounts::FsUsage;
+use librsdu::recent::modified_since;
+use librsdu::{sort_direct_children, FileInfo, Metric, Scan, ScanError, ScanErrorKind, SortOrder};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// An input action, independent of the rendering backend that produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Back,
+    ToggleDirsOnly,
+    ToggleReclaimable,
+    SortBySize,
+    SortByName,
+    ToggleMark,
+    ToggleMemory,
+    TogglePathSizes,
+    ToggleSlack,
+    SortBySlack,
+    SortBySubdirs,
+    CycleMetric,
+    ToggleExclusive,
+    Quit,
+}
+
+/// Holds the application state for navigation.
+#[allow(clippy::struct_excessive_bools)] // Independent display toggles
+pub struct AppState {
+    pub stack: Vec<FileInfo>,
+    pub selected_index: usize,
+    pub scroll_offset: usize, // Index of the first entry shown in the viewport
+    pub dirs_only: bool,      // Hide non-directory entries from the listing
+    pub annotations: Annotations,
+    pub case_sensitive_sort: bool, // Whether sorting by name distinguishes case
+    pub scan_errors: Vec<ScanError>, // Entries that could not be read during the scan
+    pub fs_usage: Option<FsUsage>, // Capacity of the filesystem holding the scan root
+    pub show_reclaimable: bool,    // Show the space deleting the selection would free
+    pub marked: BTreeMap<PathBuf, u64>, // Entries marked for the delete script, with their size on disk
+    pub memory_readout: Option<u64>,    // Estimated memory held by the scanned tree, while shown
+    pub show_path_sizes: bool,          // Annotate each header path segment with its size
+    pub size_style: SizeStyle,          // How sizes are written
+    pub show_slack: bool,               // Add a column of allocated beyond apparent size
+    pub incomplete: Option<String>,     // Footer note when the scan stopped early
+    pub classify: bool,                 // Show directory names with a trailing `/`
+    pub metric: Metric, // What the size column, bar graph, percentages and size sort measure
+    pub modified_within: Option<Duration>, // Only entries modified this recently are shown
+    pub name_filter: Option<String>, // Only entries whose names contain this, ignoring case, are shown
+    unfiltered: Option<FileInfo>,    // The whole tree, while a time filter is applied
+    pub resume_at: Option<PathBuf>,  // Saved location to offer when browsing starts
+    pub duplicates: Option<Duplicates>, // Files with identical contents, once looked for
+    pub idle_reset: Option<Duration>, // Return to the root after this long without input
+    pub idle_quit: Option<Duration>, // Quit after this long without input
+    pub refresh: Option<Duration>,   // Rescan the tree this often
+    sort_order: Option<SortOrder>,   // Order chosen by the user; `None` keeps scan order
+    sorted: HashMap<PathBuf, SortOrder>, // Directories whose children are already in an order
+}
+
+impl AppState {
+    /// Creates a state positioned at the top of `root`.
+    pub fn new(root: FileInfo) -> Self {
+        Self {
+            stack: vec![root],
+            selected_index: 0,
+            scroll_offset: 0,
+            dirs_only: false,
+            annotations: Annotations::default(),
+            case_sensitive_sort: false,
+            scan_errors: Vec::new(),
+            fs_usage: None,
+            show_reclaimable: false,
+            marked: BTreeMap::new(),
+            memory_readout: None,
+            show_path_sizes: false,
+            size_style: SizeStyle::Binary,
+            show_slack: false,
+            incomplete: None,
+            classify: false,
+            metric: Metric::Apparent,
+            modified_within: None,
+            name_filter: None,
+            unfiltered: None,
+            resume_at: None,
+            duplicates: None,
+            idle_reset: None,
+            idle_quit: None,
+            refresh: None,
+            sort_order: None,
+            sorted: HashMap::new(),
+        }
+    }
+
+    /// Returns the directory currently being viewed.
+    pub fn current_dir(&self) -> &FileInfo {
+        self.stack
+            .last()
+            .expect("navigation stack always holds the root")
+    }
+
+    /// Returns the visible entries of the directory currently being viewed.
+    ///
+    /// With `dirs_only` set, files are left out; they still count towards directory sizes.
+    /// With a `name_filter`, so is every entry whose name does not contain it.
+    pub fn entries(&self) -> Vec<&FileInfo> {
+        let needle = self.name_filter.as_deref().map(str::to_lowercase);
+        self.current_dir()
+            .children
+            .as_deref()
+            .unwrap_or(&[])
+            .iter()
+            .filter(|entry| !self.dirs_only || entry.is_dir)
+            .filter(|entry| {
+                needle.as_deref().is_none_or(|needle| {
+                    entry
+                        .path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().to_lowercase().contains(needle))
+                })
+            })
+            .collect()
+    }
+
+    /// Shows only entries of the directory being viewed whose names contain `filter`,
+    /// ignoring case. `None` or an empty filter shows them all again.
+    ///
+    /// The filter is dropped when another directory is opened.
+    pub fn set_name_filter(&mut self, filter: Option<String>) {
+        self.name_filter = filter.filter(|filter| !filter.is_empty());
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+    }
+
+    /// Explains why the listing is empty, or returns `None` if it has entries.
+    pub fn empty_listing_message(&self) -> Option<&'static str> {
+        if !self.entries().is_empty() {
+            None
+        } else if self.name_filter.is_some() {
+            Some("No entries match the search.")
+        } else if self
+            .current_dir()
+            .children
+            .as_ref()
+            .is_some_and(|children| !children.is_empty())
+        {
+            Some("No subdirectories here; files are hidden.")
+        } else {
+            Some("This directory is empty.")
+        }
+    }
+
+    /// Describes the scan errors for the footer, e.g. `Errors: 12 (3 in use / locked)`.
+    ///
+    /// Returns `None` when the scan was clean.
+    pub fn error_summary(&self) -> Option<String> {
+        if self.scan_errors.is_empty() {
+            return None;
+        }
+        let in_use = self
+            .scan_errors
+            .iter()
+            .filter(|error| error.kind == ScanErrorKind::InUse)
+            .count();
+        Some(if in_use == 0 {
+            format!("Errors: {}", self.scan_errors.len())
+        } else {
+            format!(
+                "Errors: {} ({in_use} {})",
+                self.scan_errors.len(),
+                ScanErrorKind::InUse
+            )
+        })
+    }
+
+    /// Marks the selected entry for the delete script, or unmarks it if it was marked.
+    pub fn toggle_mark(&mut self) {
+        let Some(entry) = self.entries().get(self.selected_index).copied() else {
+            return;
+        };
+        let (path, size) = (entry.path.clone(), entry.allocated_size);
+        if self.marked.remove(&path).is_none() {
+            self.marked.insert(path, size);
+        }
+    }
+
+    /// Moves the selection up by one entry.
+    pub const fn select_previous(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    /// Moves the selection down by one entry.
+    pub fn select_next(&mut self) {
+        if self.selected_index + 1 < self.entries().len() {
+            self.selected_index += 1;
+        }
+    }
+
+    /// Opens the entry at `index` if it is a directory. Returns `true` on success.
+    pub fn enter(&mut self, index: usize) -> bool {
+        match self.entries().get(index) {
+            Some(entry) if entry.is_dir => {
+                let entry = (*entry).clone();
+                self.stack.push(entry);
+                self.name_filter = None;
+                self.sort_current_dir();
+                self.selected_index = 0;
+                self.scroll_offset = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Goes up one directory. Returns `false` when already at the root.
+    pub fn go_back(&mut self) -> bool {
+        if self.stack.len() > 1 {
+            // Hand the directory back to its parent, so its sorted order is kept for next time.
+            let dir = self.stack.pop().expect("stack has more than one level");
+            if let Some(slot) = self
+                .stack
+                .last_mut()
+                .and_then(|parent| parent.children.as_mut())
+                .and_then(|children| children.iter_mut().find(|child| child.path == dir.path))
+            {
+                *slot = dir;
+            }
+            self.name_filter = None;
+            self.sort_current_dir();
+            self.selected_index = 0;
+            self.scroll_offset = 0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Closes every open directory and selects the first entry of the root.
+    pub fn reset_to_root(&mut self) {
+        while self.go_back() {}
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+    }
+
+    /// Opens the directories leading to `path` and selects it.
+    ///
+    /// Returns `false`, leaving the view at the root, if `path` is not in the tree. Files
+    /// are shown again if they were hidden and `path` is a file.
+    pub fn reveal(&mut self, path: &Path) -> bool {
+        while self.go_back() {}
+        self.name_filter = None;
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+        loop {
+            let entries = self.entries();
+            if let Some(index) = entries.iter().position(|entry| entry.path == path) {
+                self.selected_index = index;
+                return true;
+            }
+            if let Some(index) = entries
+                .iter()
+                .position(|entry| entry.is_dir && path.starts_with(&entry.path))
+            {
+                self.enter(index);
+            } else if self.dirs_only {
+                self.dirs_only = false; // The target is a file hidden from the listing
+            } else {
+                while self.go_back() {}
+                return false;
+            }
+        }
+    }
+
+    /// Opens the directory at `path`, entering every directory above it.
+    ///
+    /// Returns `false`, leaving the view at the top, if `path` is not a directory in the tree.
+    pub fn open(&mut self, path: &Path) -> bool {
+        if !self.reveal(path) {
+            return false;
+        }
+        if self.enter(self.selected_index) {
+            return true;
+        }
+        while self.go_back() {}
+        false
+    }
+
+    /// Compares file contents across the whole scanned tree, even while a time filter hides
+    /// part of it, and keeps the result for the listing and status line.
+    pub fn find_duplicates(&mut self) {
+        let root = self.unfiltered.as_ref().unwrap_or(&self.stack[0]);
+        self.duplicates = Some(find_duplicates(root));
+    }
+
+    /// Shows only what was modified within `window` of now, with directory totals counting
+    /// just those entries, or the whole tree again for `None`.
+    ///
+    /// The directory being viewed stays open if it is still in the tree.
+    pub fn filter_modified_within(&mut self, window: Option<Duration>) {
+        let current = self.current_dir().path.clone();
+        while self.go_back() {}
+        let shown = self
+            .stack
+            .pop()
+            .expect("navigation stack always holds the root");
+        let full = self.unfiltered.take().unwrap_or(shown);
+
+        let root = match window {
+            Some(window) => {
+                let cutoff = SystemTime::now()
+                    .checked_sub(window)
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                let filtered = modified_since(&full, cutoff);
+                self.unfiltered = Some(full);
+                filtered
+            }
+            None => full,
+        };
+        self.modified_within = window;
+        self.stack.push(root);
+        self.sorted.clear(); // The filtered tree is a fresh copy in scan order
+        self.sort_current_dir();
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+        if current != self.current_dir().path {
+            self.open(&current);
+        }
+    }
+
+    /// Swaps in a fresh scan of the same root, keeping the open directory, selection and
+    /// search where they still exist. Marks, notes and display settings are kept too.
+    pub fn replace_scan(&mut self, scan: Scan) {
+        let current = self.current_dir().path.clone();
+        let selected = self
+            .entries()
+            .get(self.selected_index)
+            .map(|entry| entry.path.clone());
+        let name_filter = self.name_filter.take();
+
+        self.stack = vec![scan.root];
+        self.unfiltered = None;
+        self.sorted.clear(); // The new tree is in scan order
+        self.scan_errors = scan.errors;
+        self.incomplete = scan
+            .incomplete
+            .then(|| "Incomplete: scan timed out".to_string());
+        self.duplicates = None; // Found in the old tree
+        if self.memory_readout.is_some() {
+            self.memory_readout = Some(self.stack[0].memory_usage());
+        }
+        if self.modified_within.is_some() {
+            self.filter_modified_within(self.modified_within);
+        } else {
+            self.sort_current_dir();
+        }
+
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+        if !selected.as_deref().is_some_and(|path| self.reveal(path)) {
+            self.open(&current);
+        }
+        if self.current_dir().path == current {
+            let selected_path = selected.or_else(|| {
+                self.entries()
+                    .get(self.selected_index)
+                    .map(|entry| entry.path.clone())
+            });
+            self.name_filter = name_filter;
+            self.selected_index = self
+                .entries()
+                .iter()
+                .position(|entry| Some(&entry.path) == selected_path.as_ref())
+                .unwrap_or(0);
+        }
+    }
+
+    /// Switches to `order`, keeping the selected entry selected.
+    ///
+    /// Only the directory being viewed is sorted now. Others are sorted when they are opened,
+    /// and remember their order, so each directory is sorted at most once per order.
+    pub fn sort(&mut self, order: SortOrder) {
+        let selected_path = self
+            .entries()
+            .get(self.selected_index)
+            .map(|entry| entry.path.clone());
+
+        self.sort_order = Some(order);
+        self.sort_current_dir();
+
+        if let Some(path) = selected_path {
+            self.selected_index = self
+                .entries()
+                .iter()
+                .position(|entry| entry.path == path)
+                .unwrap_or(0);
+        }
+    }
+
+    /// Switches to the next metric. Unless the listing is sorted by name, it is re-sorted by
+    /// the new metric.
+    pub fn cycle_metric(&mut self) {
+        self.metric = self.metric.next();
+        if !matches!(self.sort_order, Some(SortOrder::Name { .. })) {
+            self.sort(self.metric.sort_order());
+        }
+    }
+
+    /// Switches between exclusive sizes, which count only the files directly inside each
+    /// directory, and full apparent sizes. Unless the listing is sorted by name, it is
+    /// re-sorted by the new metric.
+    pub fn toggle_exclusive(&mut self) {
+        self.metric = if self.metric == Metric::Exclusive {
+            Metric::Apparent
+        } else {
+            Metric::Exclusive
+        };
+        if !matches!(self.sort_order, Some(SortOrder::Name { .. })) {
+            self.sort(self.metric.sort_order());
+        }
+    }
+
+    /// Returns the order chosen by the user, or `None` while entries are in scan order.
+    pub const fn sort_order(&self) -> Option<SortOrder> {
+        self.sort_order
+    }
+
+    /// Sorts the children of the directory being viewed, unless they are already in the
+    /// chosen order.
+    fn sort_current_dir(&mut self) {
+        let Some(order) = self.sort_order else {
+            return;
+        };
+        let dir = self
+            .stack
+            .last_mut()
+            .expect("navigation stack always holds the root");
+        if self.sorted.get(&dir.path) != Some(&order) {
+            sort_direct_children(dir, order);
+            self.sorted.insert(dir.path.clone(), order);
+        }
+    }
+
+    /// Pulls the selection back inside the visible entries after the listing changed.
+    ///
+    /// Anything that can shrink the listing (hiding files, filtering, deleting) leaves
+    /// `selected_index` pointing past the end. An empty listing selects index 0, which
+    /// `entries().get()` reports as nothing selected.
+    pub fn clamp_selection(&mut self) {
+        let last_index = self.entries().len().saturating_sub(1);
+        self.selected_index = self.selected_index.min(last_index);
+        self.scroll_offset = self.scroll_offset.min(self.selected_index);
+    }
+
+    /// Adjusts `scroll_offset` so the selected entry is inside a viewport of `viewport_rows`.
+    pub const fn scroll_to_selection(&mut self, viewport_rows: usize) {
+        self.scroll_offset =
+            scroll_offset_for(self.selected_index, self.scroll_offset, viewport_rows);
+    }
+}
+
+/// Something the browser does by itself once enough time has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimedEvent {
+    Quit,
+    ResetToRoot,
+    Refresh,
+}
+
+/// Keeps track of when `--idle-quit`, `--idle-reset` and `--refresh` are next due.
+pub struct Timers {
+    idle_quit: Option<Duration>,
+    idle_reset: Option<Duration>,
+    refresh: Option<Duration>,
+    last_input: Instant,
+    last_refresh: Instant,
+    reset_done: bool, // The view went back to the root since the last key
+}
+
+impl Timers {
+    /// Starts the timers of `app_state` at `now`.
+    pub const fn new(app_state: &AppState, now: Instant) -> Self {
+        Self {
+            idle_quit: app_state.idle_quit,
+            idle_reset: app_state.idle_reset,
+            refresh: app_state.refresh,
+            last_input: now,
+            last_refresh: now,
+            reset_done: false,
+        }
+    }
+
+    /// Records a key press at `now`, restarting the idle timers.
+    pub const fn key_pressed(&mut self, now: Instant) {
+        self.last_input = now;
+        self.reset_done = false;
+    }
+
+    /// Returns how long from `now` until the next timer is due, or `None` if none are set.
+    pub fn wait(&self, now: Instant) -> Option<Duration> {
+        let refresh = self.refresh.map(|every| self.last_refresh + every);
+        [self.idle_deadline(), refresh]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|deadline| deadline.saturating_duration_since(now))
+    }
+
+    /// Returns how long from `now` until an idle timer is due, leaving out `--refresh`.
+    ///
+    /// Prompts and lists give up after this long, so the idle timers still fire from them.
+    pub fn idle_wait(&self, now: Instant) -> Option<Duration> {
+        self.idle_deadline()
+            .map(|deadline| deadline.saturating_duration_since(now))
+    }
+
+    /// Returns the event due at `now`, if any, and starts waiting for the next one.
+    pub fn due(&mut self, now: Instant) -> Option<TimedEvent> {
+        let after =
+            |start: Instant, wait: Option<Duration>| wait.is_some_and(|wait| now >= start + wait);
+        if after(self.last_input, self.idle_quit) {
+            Some(TimedEvent::Quit)
+        } else if after(self.last_refresh, self.refresh) {
+            self.last_refresh = now;
+            Some(TimedEvent::Refresh)
+        } else if !self.reset_done && after(self.last_input, self.idle_reset) {
+            self.reset_done = true;
+            Some(TimedEvent::ResetToRoot)
+        } else {
+            None
+        }
+    }
+
+    /// Returns when the earliest idle timer is due.
+    fn idle_deadline(&self) -> Option<Instant> {
+        let reset = self.idle_reset.filter(|_| !self.reset_done);
+        [self.idle_quit, reset]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|wait| self.last_input + wait)
+    }
+}
+
+/// Returns the scroll offset that keeps `selected` inside a viewport of `viewport_rows`,
+/// moving the current `offset` as little as possible.
+pub const fn scroll_offset_for(selected: usize, offset: usize, viewport_rows: usize) -> usize {
+    if selected < offset {
+        selected
+    } else if viewport_rows > 0 && selected >= offset + viewport_rows {
+        selected + 1 - viewport_rows
+    } else {
+        offset
+    }
+}
+
+/// Returns where a movement `key` takes the selection `selected` in a list of `len` entries,
+/// with `page` entries per page. Other keys leave the selection where it is.
+pub const fn moved_selection(key: Key, selected: usize, len: usize, page: usize) -> usize {
+    let last_index = len.saturating_sub(1);
+    match key {
+        Key::Up => selected.saturating_sub(1),
+        Key::Down if selected < last_index => selected + 1,
+        Key::PageUp => selected.saturating_sub(page),
+        Key::PageDown => {
+            let target = selected + page;
+            if target < last_index {
+                target
+            } else {
+                last_index
+            }
+        }
+        Key::Home => 0,
+        Key::End => last_index,
+        _ => selected,
+    }
+}
+
+/// Applies `key` to the navigation state, given a viewport of `viewport_rows` entries.
+///
+/// Returns `false` when the key asks to quit.
+pub fn apply_key(app_state: &mut AppState, key: Key, viewport_rows: usize) -> bool {
+    match key {
+        Key::Up => app_state.select_previous(),
+        Key::Down => app_state.select_next(),
+        Key::PageUp | Key::PageDown | Key::Home | Key::End => {
+            app_state.selected_index = moved_selection(
+                key,
+                app_state.selected_index,
+                app_state.entries().len(),
+                viewport_rows.max(1),
+            );
+        }
+        Key::Enter => {
+            app_state.enter(app_state.selected_index);
+        }
+        Key::Back => {
+            app_state.go_back();
+        }
+        Key::ToggleDirsOnly => {
+            app_state.dirs_only = !app_state.dirs_only;
+            app_state.selected_index = 0;
+            app_state.scroll_offset = 0;
+        }
+        Key::ToggleReclaimable => app_state.show_reclaimable = !app_state.show_reclaimable,
+        Key::SortBySize => app_state.sort(app_state.metric.sort_order()),
+        Key::SortByName => app_state.sort(SortOrder::Name {
+            case_sensitive: app_state.case_sensitive_sort,
+        }),
+        Key::ToggleMark => {
+            app_state.toggle_mark();
+            app_state.select_next();
+        }
+        Key::ToggleMemory => {
+            app_state.memory_readout = match app_state.memory_readout {
+                Some(_) => None,
+                None => Some(app_state.stack[0].memory_usage()),
+            };
+        }
+        Key::ToggleSlack => app_state.show_slack = !app_state.show_slack,
+        Key::SortBySlack => app_state.sort(SortOrder::Slack),
+        Key::SortBySubdirs => app_state.sort(SortOrder::Subdirs),
+        Key::CycleMetric => app_state.cycle_metric(),
+        Key::ToggleExclusive => app_state.toggle_exclusive(),
+        Key::TogglePathSizes => app_state.show_path_sizes = !app_state.show_path_sizes,
+        Key::Quit => return false,
+    }
+
+    app_state.clamp_selection();
+    app_state.scroll_to_selection(viewport_rows);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A root holding `dirs` directories followed by `files` files.
+    fn tree(dirs: usize, files: usize) -> FileInfo {
+        let mut root = FileInfo::new_dir("/root".into(), 0);
+        for i in 0..dirs {
+            root.add_child(FileInfo::new_dir(format!("/root/dir{i}").into(), 0));
+        }
+        for i in 0..files {
+            root.add_child(FileInfo::new(format!("/root/file{i}").into(), 10, false, 1));
+        }
+        root
+    }
+
+    /// Removes the last `count` entries from the directory being viewed, as a delete would.
+    fn delete_last(app_state: &mut AppState, count: usize) {
+        let children = app_state
+            .stack
+            .last_mut()
+            .unwrap()
+            .children
+            .as_mut()
+            .unwrap();
+        children.truncate(children.len() - count);
+    }
+
+    #[test]
+    fn selection_is_clamped_after_entries_are_deleted() {
+        let mut app_state = AppState::new(tree(2, 8));
+        apply_key(&mut app_state, Key::End, 4);
+        assert_eq!(app_state.selected_index, 9);
+        assert_eq!(app_state.scroll_offset, 6);
+
+        delete_last(&mut app_state, 5);
+        apply_key(&mut app_state, Key::ToggleSlack, 4);
+        assert_eq!(app_state.selected_index, 4);
+        assert_eq!(app_state.scroll_offset, 4);
+        assert_eq!(
+            app_state.entries()[app_state.selected_index].path,
+            Path::new("/root/file2")
+        );
+    }
+
+    #[test]
+    fn selection_is_clamped_after_the_listing_is_filtered() {
+        let mut app_state = AppState::new(tree(2, 8));
+        apply_key(&mut app_state, Key::End, 4);
+        app_state.dirs_only = true; // Filtered outside apply_key, e.g. by a time filter
+        apply_key(&mut app_state, Key::ToggleReclaimable, 4);
+        assert_eq!(app_state.selected_index, 1);
+        assert_eq!(app_state.scroll_offset, 1);
+    }
+
+    #[test]
+    fn name_filter_matches_names_ignoring_case_until_another_directory_opens() {
+        let mut app_state = AppState::new(tree(2, 8));
+        app_state.set_name_filter(Some("DIR".to_string()));
+        let paths: Vec<_> = app_state
+            .entries()
+            .iter()
+            .map(|entry| &entry.path)
+            .collect();
+        assert_eq!(paths, [Path::new("/root/dir0"), Path::new("/root/dir1")]);
+
+        app_state.set_name_filter(Some("nothing".to_string()));
+        assert!(app_state.entries().is_empty());
+        assert_eq!(
+            app_state.empty_listing_message(),
+            Some("No entries match the search.")
+        );
+
+        app_state.set_name_filter(Some("dir1".to_string()));
+        apply_key(&mut app_state, Key::Enter, 10);
+        assert_eq!(app_state.current_dir().path, Path::new("/root/dir1"));
+        assert_eq!(app_state.name_filter, None);
+
+        app_state.set_name_filter(Some(String::new()));
+        assert_eq!(app_state.name_filter, None);
+    }
+
+    #[test]
+    fn timers_fire_in_turn_and_restart_on_input() {
+        let mut app_state = AppState::new(tree(0, 1));
+        app_state.idle_reset = Some(Duration::from_mins(1));
+        app_state.idle_quit = Some(Duration::from_mins(10));
+        app_state.refresh = Some(Duration::from_secs(100));
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut timers = Timers::new(&app_state, start);
+
+        assert_eq!(timers.wait(start), Some(Duration::from_mins(1)));
+        assert_eq!(timers.due(at(59)), None);
+        assert_eq!(timers.due(at(60)), Some(TimedEvent::ResetToRoot));
+        // The view is already at the root, so only the refresh is left to wait for.
+        assert_eq!(timers.wait(at(60)), Some(Duration::from_secs(40)));
+        assert_eq!(timers.due(at(61)), None);
+        assert_eq!(timers.due(at(100)), Some(TimedEvent::Refresh));
+        assert_eq!(timers.wait(at(100)), Some(Duration::from_secs(100)));
+
+        timers.key_pressed(at(150));
+        assert_eq!(timers.idle_wait(at(150)), Some(Duration::from_mins(1)));
+        assert_eq!(timers.wait(at(150)), Some(Duration::from_secs(50)));
+        assert_eq!(timers.due(at(210)), Some(TimedEvent::Refresh));
+        assert_eq!(timers.due(at(210)), Some(TimedEvent::ResetToRoot));
+        assert_eq!(timers.due(at(750)), Some(TimedEvent::Quit));
+    }
+
+    #[test]
+    fn no_timers_means_no_wait() {
+        let timers = Timers::new(&AppState::new(tree(0, 1)), Instant::now());
+        assert_eq!(timers.wait(Instant::now()), None);
+        assert_eq!(timers.idle_wait(Instant::now()), None);
+    }
+
+    #[test]
+    fn replace_scan_keeps_the_open_directory_and_selection() {
+        let nested = |files: usize| {
+            let mut dir = FileInfo::new_dir("/root/dir0".into(), 0);
+            for i in 0..files {
+                dir.add_child(FileInfo::new(
+                    format!("/root/dir0/file{i}").into(),
+                    10,
+                    false,
+                    1,
+                ));
+            }
+            FileInfo::new_dir("/root".into(), 0).with_child(dir)
+        };
+        let rescanned = |root| Scan {
+            root,
+            errors: Vec::new(),
+            incomplete: false,
+        };
+        let mut app_state = AppState::new(nested(3));
+        apply_key(&mut app_state, Key::Enter, 10);
+        apply_key(&mut app_state, Key::End, 10);
+
+        app_state.replace_scan(rescanned(nested(5)));
+        assert_eq!(app_state.current_dir().path, Path::new("/root/dir0"));
+        assert_eq!(app_state.current_dir().children.as_ref().unwrap().len(), 5);
+        assert_eq!(
+            app_state.entries()[app_state.selected_index].path,
+            Path::new("/root/dir0/file2")
+        );
+
+        // With the selection gone, the directory stays open.
+        app_state.replace_scan(rescanned(nested(1)));
+        assert_eq!(app_state.current_dir().path, Path::new("/root/dir0"));
+        assert_eq!(app_state.selected_index, 0);
+
+        // With the directory gone too, the view goes back to the root.
+        app_state.replace_scan(rescanned(FileInfo::new_dir("/root".into(), 0)));
+        assert_eq!(app_state.stack.len(), 1);
+    }
+
+    #[test]
+    fn selection_is_clamped_when_the_listing_empties() {
+        let mut app_state = AppState::new(tree(0, 3));
+        apply_key(&mut app_state, Key::End, 10);
+        delete_last(&mut app_state, 3);
+        apply_key(&mut app_state, Key::ToggleSlack, 10);
+        assert_eq!(app_state.selected_index, 0);
+        assert_eq!(app_state.scroll_offset, 0);
+        assert!(app_state.entries().get(app_state.selected_index).is_none());
+        assert!(apply_key(&mut app_state, Key::Down, 10));
+        assert_eq!(app_state.selected_index, 0);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::annotations::Annotations;
+use crate::format::{entry_name, generate_bar, SizeStyle};
+use librsdu::duplicates::Duplicates;
+use librsdu::{percentage, FileInfo, Metric};
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Width of the bar graph inside its brackets.
+const BAR_WIDTH: usize = 30;
+
+/// How a column's value is padded within its width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Information shared by every row of a listing, available to column extractors.
+pub struct RowContext<'a> {
+    pub max_size: u64, // Largest entry in the listing by `metric`, for scaling the bar graph
+    pub dir_size: u64, // The listed directory's `metric`, for percentages
+    pub annotations: &'a Annotations,
+    pub marked: &'a BTreeMap<PathBuf, u64>, // Entries marked for the delete script
+    pub size_style: SizeStyle,
+    pub metric: Metric, // What the size column, bar graph and percentages measure
+    pub classify: bool, // Append `/` to directory names, like `ls -F`
+    pub duplicates: Option<&'a Duplicates>, // Files known to have copies elsewhere
+}
+
+/// Produces a column's text for one entry.
+pub type Extractor = Box<dyn Fn(&FileInfo, &RowContext) -> String>;
+
+/// One column of the directory listing.
+pub struct Column {
+    pub name: &'static str,
+    pub width: Option<usize>, // `None` lets the column run to the end of the line
+    pub align: Align,
+    pub value: Extractor,
+}
+
+impl Column {
+    /// Creates a column from its header name, layout and value extractor.
+    pub fn new(
+        name: &'static str,
+        width: Option<usize>,
+        align: Align,
+        value: impl Fn(&FileInfo, &RowContext) -> String + 'static,
+    ) -> Self {
+        Self {
+            name,
+            width,
+            align,
+            value: Box::new(value),
+        }
+    }
+
+    /// Pads or truncates `text` to `width`, the column's width in the current layout.
+    fn fit(&self, text: &str, width: Option<usize>) -> String {
+        let Some(width) = width else {
+            return text.to_string();
+        };
+        let text: String = text.chars().take(width).collect();
+        match self.align {
+            Align::Left => format!("{text:<width$}"),
+            Align::Right => format!("{text:>width$}"),
+        }
+    }
+}
+
+/// Returns the width of each column when listing `entries`.
+///
+/// Right-aligned columns (sizes, counts and percentages) grow past their usual width to fit
+/// their widest value, so petabyte totals or huge item counts are never cut off and every
+/// row stays aligned. Other columns keep their width.
+pub fn fitted_widths<C: Borrow<Column>>(
+    columns: &[C],
+    entries: &[&FileInfo],
+    context: &RowContext,
+) -> Vec<Option<usize>> {
+    columns
+        .iter()
+        .map(Borrow::borrow)
+        .map(|column: &Column| match column.width {
+            Some(width) if column.align == Align::Right => Some(
+                entries
+                    .iter()
+                    .map(|entry| (column.value)(entry, context).chars().count())
+                    .fold(width, usize::max),
+            ),
+            width => width,
+        })
+        .collect()
+}
+
+/// Renders the column headers, laid out like [`render_row`].
+pub fn render_header<C: Borrow<Column>>(columns: &[C], widths: &[Option<usize>]) -> String {
+    columns
+        .iter()
+        .zip(widths)
+        .map(|(column, &width)| column.borrow().fit(column.borrow().name, width))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Renders one entry as a line of space-separated columns, with the widths from
+/// [`fitted_widths`].
+pub fn render_row<C: Borrow<Column>>(
+    columns: &[C],
+    widths: &[Option<usize>],
+    entry: &FileInfo,
+    context: &RowContext,
+) -> String {
+    columns
+        .iter()
+        .map(Borrow::borrow)
+        .zip(widths)
+        .map(|(column, &width): (&Column, _)| column.fit(&(column.value)(entry, context), width))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The built-in columns that can be selected with `--columns`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColumnKind {
+    /// `x` for entries marked for the delete script, `*` for annotated ones and `=` for
+    /// files with a copy elsewhere, once duplicates have been looked for.
+    Flag,
+    /// Apparent size.
+    Size,
+    /// Size allocated on disk.
+    Allocated,
+    /// Allocated size beyond the apparent size, lost to block rounding.
+    Slack,
+    /// Number of entries inside a directory, not counting itself. Blank for files.
+    Items,
+    /// Number of directories directly inside a directory. Blank for files.
+    Subdirs,
+    /// Share of the current directory's size.
+    Percent,
+    /// Bar graph relative to the largest entry.
+    Bar,
+    /// File name.
+    Name,
+}
+
+/// Returns `columns` with `extra` inserted before the column that runs to the end of the
+/// line, unless a column with the same name is already shown.
+pub fn with_column<'a>(columns: &[&'a Column], extra: &'a Column) -> Vec<&'a Column> {
+    let mut shown = columns.to_vec();
+    if !columns.iter().any(|column| column.name == extra.name) {
+        let position = columns
+            .iter()
+            .position(|column| column.width.is_none())
+            .unwrap_or(columns.len());
+        shown.insert(position, extra);
+    }
+    shown
+}
+
+/// The columns shown when `--columns` is not given.
+pub const DEFAULT_COLUMNS: &[ColumnKind] = &[
+    ColumnKind::Flag,
+    ColumnKind::Size,
+    ColumnKind::Bar,
+    ColumnKind::Name,
+];
+
+impl ColumnKind {
+    /// Builds the column definition for this kind.
+    pub fn column(self) -> Column {
+        match self {
+            Self::Flag => Column::new("", Some(3), Align::Left, |entry, context| {
+                let mark = if context.marked.contains_key(&entry.path) {
+                    'x'
+                } else {
+                    ' '
+                };
+                let note = if context.annotations.get(&entry.path).is_some() {
+                    '*'
+                } else {
+                    ' '
+                };
+                let copy = if context
+                    .duplicates
+                    .is_some_and(|duplicates| duplicates.is_duplicate(&entry.path))
+                {
+                    '='
+                } else {
+                    ' '
+                };
+                format!("{mark}{note}{copy}")
+            }),
+            Self::Size => Column::new("Size", Some(10), Align::Right, |entry, context| {
+                let value = context.metric.value(entry);
+                if context.metric.is_size() {
+                    context.size_style.format(value)
+                } else {
+                    value.to_string()
+                }
+            }),
+            Self::Allocated => {
+                Column::new("Allocated", Some(10), Align::Right, |entry, context| {
+                    context.size_style.format(entry.allocated_size)
+                })
+            }
+            Self::Slack => Column::new("Slack", Some(10), Align::Right, |entry, context| {
+                context.size_style.format(entry.slack())
+            }),
+            Self::Subdirs => Column::new("Subdirs", Some(7), Align::Right, |entry, _| {
+                if entry.is_dir {
+                    entry.subdirectory_count().to_string()
+                } else {
+                    String::new()
+                }
+            }),
+            Self::Items => Column::new("Items", Some(8), Align::Right, |entry, _| {
+                if entry.is_dir {
+                    entry.contained_items().to_string()
+                } else {
+                    String::new()
+                }
+            }),
+            Self::Percent => Column::new("Percent", Some(7), Align::Right, |entry, context| {
+                format!(
+                    "{:.1}%",
+                    percentage(context.metric.value(entry), context.dir_size)
+                )
+            }),
+            Self::Bar => Column::new("", Some(BAR_WIDTH + 2), Align::Left, |entry, context| {
+                format!(
+                    "[{}]",
+                    generate_bar(context.metric.value(entry), context.max_size, BAR_WIDTH)
+                )
+            }),
+            Self::Name => Column::new("Name", None, Align::Left, |entry, context| {
+                if context.classify && entry.is_dir {
+                    format!("{}/", entry_name(entry))
+                } else {
+                    entry_name(entry)
+                }
+            }),
+        }
+    }
+}
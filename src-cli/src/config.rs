@@ -0,0 +1,71 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Settings read from the rsdu configuration file.
+///
+/// The file lives at `rsdu/config` in the XDG config directory (`~/.config/rsdu/config` on
+/// Linux) and uses an INI-like layout: `[section]` headers followed by `name = value` lines.
+/// Blank lines and lines starting with `#` are ignored.
+///
+/// ```text
+/// [keys]
+/// up = k Up
+/// down = j Down
+/// quit = q
+/// ```
+#[derive(Debug, Default)]
+pub struct Config {
+    pub keys: Vec<(String, String)>, // Action name and key list from the `[keys]` section
+}
+
+impl Config {
+    /// Returns the path of the configuration file, if a config directory is known.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("rsdu").join("config"))
+    }
+
+    /// Loads the configuration file. A missing file yields the default configuration.
+    pub fn load() -> io::Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Self::parse(&contents).map_err(|message| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {message}", path.display()),
+                )
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Parses the contents of a configuration file.
+    fn parse(contents: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut section = String::new();
+
+        for (number, line) in contents.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+            let Some((name, value)) = line.split_once('=') else {
+                return Err(format!("line {number}: expected 'name = value'"));
+            };
+            let entry = (name.trim().to_string(), value.trim().to_string());
+            match section.as_str() {
+                "keys" => config.keys.push(entry),
+                "" => return Err(format!("line {number}: setting outside of a section")),
+                other => return Err(format!("line {number}: unknown section '[{other}]'")),
+            }
+        }
+
+        Ok(config)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::app::{moved_selection, scroll_offset_for, Key};
+use librsdu::{ScanError, ScanErrorKind};
+
+/// A scrollable, filterable view of the errors collected during the scan.
+///
+/// Errors are grouped by kind, then sorted by path within each group.
+pub struct ErrorList<'a> {
+    errors: Vec<&'a ScanError>,
+    filter: String, // Case-insensitive text the path or message must contain
+    pub selected_index: usize,
+    pub scroll_offset: usize,
+}
+
+impl<'a> ErrorList<'a> {
+    /// Creates an unfiltered list of `errors`, positioned at the top.
+    pub fn new(errors: &'a [ScanError]) -> Self {
+        let mut errors: Vec<&ScanError> = errors.iter().collect();
+        errors.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.path.cmp(&b.path)));
+        Self {
+            errors,
+            filter: String::new(),
+            selected_index: 0,
+            scroll_offset: 0,
+        }
+    }
+
+    /// Counts the errors of each kind, e.g. `12 errors: 10 permission denied, 2 other error`.
+    pub fn summary(&self) -> String {
+        let mut counts: Vec<(ScanErrorKind, usize)> = Vec::new();
+        for error in &self.errors {
+            match counts.last_mut() {
+                Some((kind, count)) if *kind == error.kind => *count += 1,
+                _ => counts.push((error.kind, 1)),
+            }
+        }
+        let counts: Vec<String> = counts
+            .iter()
+            .map(|(kind, count)| format!("{count} {kind}"))
+            .collect();
+        format!("{} errors: {}", self.errors.len(), counts.join(", "))
+    }
+
+    /// Returns the current filter text.
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Shows only errors whose path or message contains `filter`, ignoring case.
+    pub fn set_filter(&mut self, filter: String) {
+        self.filter = filter;
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+    }
+
+    /// Returns the errors that match the filter.
+    pub fn visible(&self) -> Vec<&'a ScanError> {
+        let filter = self.filter.to_lowercase();
+        self.errors
+            .iter()
+            .copied()
+            .filter(|error| {
+                filter.is_empty()
+                    || error
+                        .path
+                        .to_string_lossy()
+                        .to_lowercase()
+                        .contains(&filter)
+                    || error.message.to_lowercase().contains(&filter)
+            })
+            .collect()
+    }
+
+    /// Applies a movement `key` in a viewport of `viewport_rows`. Returns `false` for keys
+    /// that close the list.
+    pub fn apply_key(&mut self, key: Key, viewport_rows: usize) -> bool {
+        if matches!(key, Key::Back | Key::Quit) {
+            return false;
+        }
+        let page = viewport_rows.max(1);
+        self.selected_index = moved_selection(key, self.selected_index, self.visible().len(), page);
+        self.scroll_offset =
+            scroll_offset_for(self.selected_index, self.scroll_offset, viewport_rows);
+        true
+    }
+}
+
+/// Formats one error as `[kind] path: message`.
+pub fn error_line(error: &ScanError) -> String {
+    format!("[{}] {error}", error.kind)
+}
@@ -0,0 +1,355 @@
+use librsdu::{percentage, FileInfo};
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+/// Returns the name to display for an entry: its file name, or the full path for the root.
+pub fn entry_name(entry: &FileInfo) -> String {
+    entry
+        .path
+        .file_name()
+        .unwrap_or_else(|| entry.path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+pub fn generate_bar(size: u64, max_size: u64, bar_width: usize) -> String {
+    let ratio = percentage(size, max_size) / 100.0;
+    // `percentage` already caps the ratio at 1, but a size above `max_size` (e.g. from a
+    // rescan) must never make the padding below underflow.
+    let filled_length = ((ratio * bar_width as f64).round() as usize).min(bar_width);
+    let bar = "#".repeat(filled_length);
+    let empty = " ".repeat(bar_width - filled_length);
+    format!("{bar}{empty}")
+}
+
+/// Formats `size` with one decimal and a binary unit, e.g. `512 B`, `4.0 KiB` or `1.5 GiB`.
+///
+/// The unit is picked after rounding, so a value that would read `1024.0` of one unit is
+/// shown as `1.0` of the next: 1048575 bytes is `1.0 MiB`, never `1024.0 KiB`.
+#[allow(clippy::cast_precision_loss)]
+pub fn human_readable_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while (value * 10.0).round() >= 10240.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// How sizes are written in the listing, footer and status line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SizeStyle {
+    /// One decimal and a binary unit, e.g. `4.0 KiB`.
+    #[default]
+    Binary,
+    /// Exactly as `ls -lh` writes them, e.g. `4.0K` or `11K`.
+    Ls,
+}
+
+impl SizeStyle {
+    /// Formats `size` in this style.
+    pub fn format(self, size: u64) -> String {
+        match self {
+            Self::Binary => human_readable_size(size),
+            Self::Ls => ls_size(size),
+        }
+    }
+}
+
+/// Formats `size` the way coreutils' `ls -h` does.
+///
+/// Sizes are in powers of 1024 with a single-letter suffix and no `B`. Below 1024 the
+/// plain byte count is shown. Values are always rounded up. Below 10 one decimal is
+/// kept, so 1025 bytes is `1.1K`. From 10 up they are whole numbers, so 10241 bytes is
+/// `11K`. A value that rounds up to 1024 moves to the next unit, so 1048575 bytes is
+/// `1.0M`. This follows the integer arithmetic of gnulib's `human_readable`, so it
+/// matches `ls` byte for byte rather than approximately.
+pub fn ls_size(size: u64) -> String {
+    const BASE: u64 = 1024;
+    const SUFFIXES: [char; 8] = ['K', 'M', 'G', 'T', 'P', 'E', 'Z', 'Y'];
+
+    let mut amount = size;
+    let mut tenths = 0;
+    // What was dropped below the tenths: 0 nothing, 1 under half, 2 half, 3 over half.
+    let mut rounding = 0;
+    let mut exponent = 0;
+    let mut decimal = None;
+
+    if amount >= BASE {
+        while amount >= BASE && exponent < SUFFIXES.len() {
+            let r10 = (amount % BASE) * 10 + tenths;
+            let r2 = (r10 % BASE) * 2 + (rounding >> 1);
+            amount /= BASE;
+            tenths = r10 / BASE;
+            rounding = if r2 < BASE {
+                u64::from(r2 + rounding != 0)
+            } else {
+                2 + u64::from(BASE < r2 + rounding)
+            };
+            exponent += 1;
+        }
+
+        if amount < 10 {
+            if rounding > 0 {
+                tenths += 1;
+                rounding = 0;
+                if tenths == 10 {
+                    amount += 1;
+                    tenths = 0;
+                }
+            }
+            if amount < 10 {
+                decimal = Some(tenths);
+                tenths = 0;
+                rounding = 0;
+            }
+        }
+    }
+
+    if tenths + rounding > 0 {
+        amount += 1;
+        if amount == BASE && exponent < SUFFIXES.len() {
+            exponent += 1;
+            decimal = Some(0);
+            amount = 1;
+        }
+    }
+
+    let mut text = amount.to_string();
+    if let Some(tenths) = decimal {
+        text.push('.');
+        text.push_str(&tenths.to_string());
+    }
+    if exponent > 0 {
+        text.push(SUFFIXES[exponent - 1]);
+    }
+    text
+}
+
+/// Formats `size` compactly for tight spaces, e.g. `512B`, `4.5K` or `120G`.
+///
+/// Values below 10 keep one decimal; larger ones are rounded to whole units. The unit is
+/// chosen after rounding, so 1048575 bytes is `1.0M` rather than `1024K`.
+#[allow(clippy::cast_precision_loss)]
+pub fn short_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+    // The value as it will be written: one decimal below 10, whole units above.
+    let shown = |value: f64| {
+        let tenths = (value * 10.0).round();
+        if tenths < 100.0 {
+            tenths / 10.0
+        } else {
+            value.round()
+        }
+    };
+    if size < 1024 {
+        return format!("{size}B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while shown(value) >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let value = shown(value);
+    if value < 10.0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+/// Formats how long ago something happened in its largest whole unit, e.g. `40s`, `12m`,
+/// `3h` or `15d`.
+pub fn age(elapsed: Duration) -> String {
+    const UNITS: [(u64, &str); 3] = [(86_400, "d"), (3_600, "h"), (60, "m")];
+    let secs = elapsed.as_secs();
+    UNITS.iter().find(|&&(unit, _)| secs >= unit).map_or_else(
+        || format!("{secs}s"),
+        |&(unit, suffix)| format!("{}{suffix}", secs / unit),
+    )
+}
+
+/// Parses a size such as `4096`, `4K`, `4KiB` or `1M`. Suffixes are binary and case-insensitive.
+pub fn parse_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, suffix) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("'{text}' does not start with a number"))?;
+    let multiplier: u64 = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(format!("unknown size suffix '{suffix}'")),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("'{text}' is too large"))
+}
+
+/// Parses a duration such as `90`, `30s`, `5m`, `1.5h`, `7d` or `2w`. A bare number is in
+/// seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let (number, unit_secs) = match text.char_indices().last() {
+        Some((i, 's')) => (&text[..i], 1.0),
+        Some((i, 'm')) => (&text[..i], 60.0),
+        Some((i, 'h')) => (&text[..i], 3600.0),
+        Some((i, 'd')) => (&text[..i], 86_400.0),
+        Some((i, 'w')) => (&text[..i], 604_800.0),
+        _ => (text, 1.0),
+    };
+    let value: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid duration '{text}'; use e.g. 30s, 5m or 1h"))?;
+    Duration::try_from_secs_f64(value * unit_secs)
+        .map_err(|_| format!("duration '{text}' is out of range"))
+}
+
+/// Parses a timer interval for `--idle-reset`, `--idle-quit` or `--refresh`, which must be
+/// at least a millisecond.
+///
+/// The terminal is polled with a millisecond timeout, so anything shorter would stop key
+/// reads from waiting at all.
+pub fn parse_interval(text: &str) -> Result<Duration, String> {
+    let duration = parse_duration(text)?;
+    if duration < Duration::from_millis(1) {
+        return Err(format!("interval '{}' must be at least 1ms", text.trim()));
+    }
+    Ok(duration)
+}
+
+/// Parses a block size for `--simulate-block-size`, which must not be zero.
+pub fn parse_block_size(text: &str) -> Result<NonZeroU64, String> {
+    NonZeroU64::new(parse_size(text)?).ok_or_else(|| "block size must not be zero".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_bar_fills_in_proportion() {
+        assert_eq!(generate_bar(0, 100, 10), "          ");
+        assert_eq!(generate_bar(50, 100, 10), "#####     ");
+        assert_eq!(generate_bar(100, 100, 10), "##########");
+        assert_eq!(generate_bar(0, 0, 4), "    ");
+    }
+
+    #[test]
+    fn generate_bar_clamps_sizes_above_the_maximum() {
+        assert_eq!(generate_bar(150, 100, 10), "##########");
+        assert_eq!(generate_bar(u64::MAX, 1, 10), "##########");
+        assert_eq!(generate_bar(5, 0, 10), "          ");
+    }
+
+    #[test]
+    fn human_readable_size_switches_units_at_the_rounded_boundary() {
+        let cases = [
+            (0, "0 B"),
+            (1023, "1023 B"),
+            (1024, "1.0 KiB"),
+            (1536, "1.5 KiB"),
+            (1_048_524, "1023.9 KiB"),
+            (1_048_525, "1.0 MiB"),
+            (1_048_575, "1.0 MiB"),
+            (1_048_576, "1.0 MiB"),
+            (1_073_741_823, "1.0 GiB"),
+            (1_073_741_824, "1.0 GiB"),
+            (1 << 40, "1.0 TiB"),
+            (1 << 50, "1.0 PiB"),
+            (1 << 60, "1.0 EiB"),
+            (u64::MAX, "16.0 EiB"),
+        ];
+        for (size, expected) in cases {
+            assert_eq!(
+                human_readable_size(size),
+                expected,
+                "human_readable_size({size})"
+            );
+        }
+    }
+
+    #[test]
+    fn ls_size_matches_ls_at_the_rounding_boundaries() {
+        // Expected values are the size column of `ls -lh` for a file truncated to each size.
+        let cases = [
+            (0, "0"),
+            (1, "1"),
+            (1023, "1023"),
+            (1024, "1.0K"),
+            (1025, "1.1K"),
+            (1126, "1.1K"),
+            (1127, "1.2K"),
+            (1536, "1.5K"),
+            (10_239, "10K"),
+            (10_240, "10K"),
+            (10_241, "11K"),
+            (11_264, "11K"),
+            (11_265, "12K"),
+            (102_400, "100K"),
+            (1_047_552, "1023K"),
+            (1_047_553, "1.0M"),
+            (1_048_575, "1.0M"),
+            (1_048_576, "1.0M"),
+            (1_048_577, "1.1M"),
+            (10_485_760, "10M"),
+            (10_485_761, "11M"),
+            (1_073_741_823, "1.0G"),
+            (1_073_741_824, "1.0G"),
+            (1_099_511_627_776, "1.0T"),
+        ];
+        for (size, expected) in cases {
+            assert_eq!(ls_size(size), expected, "ls_size({size})");
+        }
+    }
+
+    #[test]
+    fn interval_must_be_at_least_a_millisecond() {
+        assert_eq!(parse_interval("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("0.001"), Ok(Duration::from_millis(1)));
+        assert!(parse_interval("0").is_err());
+        assert!(parse_interval("0s").is_err());
+        assert!(parse_interval("0.0001").is_err());
+        assert!(parse_interval("soon").is_err());
+    }
+
+    #[test]
+    fn short_size_picks_the_unit_after_rounding() {
+        let cases = [
+            (0, "0B"),
+            (1023, "1023B"),
+            (1024, "1.0K"),
+            (10_188, "9.9K"),
+            (10_239, "10K"),
+            (10_240, "10K"),
+            (1_047_552, "1023K"),
+            (1_048_063, "1023K"),
+            (1_048_064, "1.0M"),
+            (1_048_575, "1.0M"),
+            (1_048_576, "1.0M"),
+            (1_073_741_823, "1.0G"),
+            (u64::MAX, "16E"),
+        ];
+        for (size, expected) in cases {
+            assert_eq!(short_size(size), expected, "short_size({size})");
+        }
+    }
+}
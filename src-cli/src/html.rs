@@ -0,0 +1,40 @@
+use librsdu::FileInfo;
+
+/// Page template with `{{TITLE}}` and `{{DATA}}` placeholders.
+const TEMPLATE: &str = include_str!("report.html");
+
+/// Builds a self-contained HTML report of `root`: a collapsible tree with size bars.
+///
+/// The tree is embedded as JSON and rendered by an inline script, so the file needs no
+/// network access to view.
+pub fn report(root: &FileInfo) -> String {
+    // `<` only occurs inside JSON strings, where `\u003c` means the same. Escaping it keeps
+    // names such as `</script>` from ending the script element early.
+    let data = root.to_json().replace('<', "\\u003c");
+    let title = escape(&root.path.to_string_lossy());
+
+    // The title placeholders all come before the data, so names in the data are never
+    // mistaken for placeholders.
+    let (head, tail) = TEMPLATE
+        .split_once("{{DATA}}")
+        .expect("report template has a data placeholder");
+    let mut page = head.replace("{{TITLE}}", &title);
+    page.push_str(&data);
+    page.push_str(tail);
+    page
+}
+
+/// Escapes `text` for use in HTML element content.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,317 @@
+use crate::app::Key;
+use ncurses::{
+    KEY_BACKSPACE, KEY_DC, KEY_DOWN, KEY_END, KEY_ENTER, KEY_HOME, KEY_LEFT, KEY_NPAGE, KEY_PPAGE,
+    KEY_RIGHT, KEY_UP,
+};
+use std::collections::HashMap;
+
+/// Escape key code, used to cancel prompts.
+pub const KEY_ESCAPE: i32 = 27;
+
+/// A TUI command: either a navigation key or an action that needs the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Navigate(Key),
+    Annotate,
+    ShowErrors,
+    Search,
+    SameOwner,
+    RecentFiles,
+    Shell,
+    TimeFilter,
+    FindDuplicates,
+}
+
+/// Every bindable action with its configuration name and default keys.
+const ACTIONS: &[(&str, Action, &[i32])] = &[
+    ("up", Action::Navigate(Key::Up), &[KEY_UP]),
+    ("down", Action::Navigate(Key::Down), &[KEY_DOWN]),
+    ("page_up", Action::Navigate(Key::PageUp), &[KEY_PPAGE]),
+    ("page_down", Action::Navigate(Key::PageDown), &[KEY_NPAGE]),
+    ("home", Action::Navigate(Key::Home), &[KEY_HOME]),
+    ("end", Action::Navigate(Key::End), &[KEY_END]),
+    ("enter", Action::Navigate(Key::Enter), &[KEY_ENTER, 10]),
+    (
+        "back",
+        Action::Navigate(Key::Back),
+        &[KEY_BACKSPACE, 127, 8],
+    ),
+    (
+        "dirs_only",
+        Action::Navigate(Key::ToggleDirsOnly),
+        &[b'D' as i32],
+    ),
+    (
+        "space_freed",
+        Action::Navigate(Key::ToggleReclaimable),
+        &[b'f' as i32],
+    ),
+    (
+        "sort_size",
+        Action::Navigate(Key::SortBySize),
+        &[b's' as i32],
+    ),
+    (
+        "sort_name",
+        Action::Navigate(Key::SortByName),
+        &[b'n' as i32],
+    ),
+    ("mark", Action::Navigate(Key::ToggleMark), &[b'm' as i32]),
+    ("slack", Action::Navigate(Key::ToggleSlack), &[b'w' as i32]),
+    (
+        "sort_slack",
+        Action::Navigate(Key::SortBySlack),
+        &[b'W' as i32],
+    ),
+    (
+        "sort_subdirs",
+        Action::Navigate(Key::SortBySubdirs),
+        &[b'C' as i32],
+    ),
+    ("metric", Action::Navigate(Key::CycleMetric), &[b'c' as i32]),
+    (
+        "exclusive",
+        Action::Navigate(Key::ToggleExclusive),
+        &[b'x' as i32],
+    ),
+    (
+        "path_sizes",
+        Action::Navigate(Key::TogglePathSizes),
+        &[b'b' as i32],
+    ),
+    (
+        "memory",
+        Action::Navigate(Key::ToggleMemory),
+        &[b'M' as i32],
+    ),
+    ("annotate", Action::Annotate, &[b'a' as i32]),
+    ("errors", Action::ShowErrors, &[b'e' as i32]),
+    ("same_owner", Action::SameOwner, &[b'o' as i32]),
+    ("recent", Action::RecentFiles, &[b'R' as i32]),
+    ("shell", Action::Shell, &[b'S' as i32]),
+    ("time_filter", Action::TimeFilter, &[b'T' as i32]),
+    ("duplicates", Action::FindDuplicates, &[b'u' as i32]),
+    ("search", Action::Search, &[b'/' as i32]),
+    ("quit", Action::Navigate(Key::Quit), &[b'q' as i32]),
+];
+
+/// Names accepted for non-character keys, with the codes they stand for.
+const KEY_NAMES: &[(&str, &[i32])] = &[
+    ("Up", &[KEY_UP]),
+    ("Down", &[KEY_DOWN]),
+    ("Left", &[KEY_LEFT]),
+    ("Right", &[KEY_RIGHT]),
+    ("PageUp", &[KEY_PPAGE]),
+    ("PageDown", &[KEY_NPAGE]),
+    ("Home", &[KEY_HOME]),
+    ("End", &[KEY_END]),
+    ("Enter", &[KEY_ENTER, 10, 13]),
+    ("Backspace", &[KEY_BACKSPACE, 127, 8]),
+    ("Delete", &[KEY_DC]),
+    ("Escape", &[KEY_ESCAPE]),
+    ("Space", &[b' ' as i32]),
+    ("Tab", &[b'\t' as i32]),
+];
+
+/// Maps raw ncurses key codes to actions.
+pub struct KeyMap {
+    bindings: HashMap<i32, Action>,
+    primary_keys: HashMap<Action, i32>, // Key shown for each action in on-screen hints
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|(_, action, keys)| keys.iter().map(move |&key| (key, *action)))
+            .collect();
+        let primary_keys = ACTIONS
+            .iter()
+            .map(|(_, action, keys)| (*action, keys[0]))
+            .collect();
+        Self {
+            bindings,
+            primary_keys,
+        }
+    }
+}
+
+impl KeyMap {
+    /// Builds a key map from the defaults, replacing the keys of every action in `overrides`.
+    ///
+    /// Each override pairs an action name such as `quit` with a space-separated list of keys:
+    /// single characters (`q`) or key names (`Up`, `PageDown`, `Enter`, `Space`, ...).
+    ///
+    /// A key may only do one thing: giving it to two actions, or to an action while another
+    /// still has it by default, is an error. Rebind the other action too to free the key.
+    pub fn with_overrides(overrides: &[(String, String)]) -> Result<Self, String> {
+        let defaults = Self::default();
+        let mut keymap = Self::default();
+        let mut claimed: HashMap<i32, Action> = HashMap::new(); // Keys given in `overrides`
+        for (name, keys) in overrides {
+            let action = ACTIONS
+                .iter()
+                .find(|(action_name, _, _)| action_name == name)
+                .map(|&(_, action, _)| action)
+                .ok_or_else(|| format!("unknown action '{name}'"))?;
+            let codes = parse_keys(keys)?;
+            for &code in &codes {
+                match claimed.insert(code, action) {
+                    Some(other) if other != action => {
+                        return Err(format!(
+                            "key '{}' is bound to both '{}' and '{name}'",
+                            key_label(code),
+                            action_name(other)
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+
+            keymap.bindings.retain(|_, bound| *bound != action);
+            keymap.primary_keys.insert(action, codes[0]);
+            for code in codes {
+                keymap.bindings.insert(code, action);
+            }
+        }
+
+        let overridden: Vec<Action> = claimed.values().copied().collect();
+        for (&code, &action) in &claimed {
+            if let Some(&other) = defaults.bindings.get(&code) {
+                if other != action && !overridden.contains(&other) {
+                    return Err(format!(
+                        "key '{}' for '{}' is already bound to '{}'; rebind '{}' as well",
+                        key_label(code),
+                        action_name(action),
+                        action_name(other),
+                        action_name(other)
+                    ));
+                }
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Returns this key map with every key bound to one of `actions` unbound.
+    pub fn without(mut self, actions: &[Action]) -> Self {
+        self.bindings.retain(|_, bound| !actions.contains(bound));
+        self
+    }
+
+    /// Returns `true` if some key is bound to `action`.
+    pub fn is_bound(&self, action: Action) -> bool {
+        self.bindings.values().any(|&bound| bound == action)
+    }
+
+    /// Returns the action bound to the raw key code `ch`.
+    pub fn action(&self, ch: i32) -> Option<Action> {
+        self.bindings.get(&ch).copied()
+    }
+
+    /// Returns a short label for the key bound to `action`, for on-screen hints.
+    pub fn label(&self, action: Action) -> String {
+        self.primary_keys
+            .get(&action)
+            .filter(|&&code| self.action(code) == Some(action))
+            .map_or_else(|| "?".to_string(), |&code| key_label(code))
+    }
+}
+
+/// Returns the configuration name of `action`.
+fn action_name(action: Action) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|&&(_, known, _)| known == action)
+        .map_or("?", |&(name, _, _)| name)
+}
+
+/// Parses a space-separated list of key names into key codes.
+fn parse_keys(keys: &str) -> Result<Vec<i32>, String> {
+    let mut codes = Vec::new();
+    for key in keys.split_whitespace() {
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii() && !c.is_ascii_control() => codes.push(c as i32),
+            _ => {
+                let (_, named) = KEY_NAMES
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                    .ok_or_else(|| format!("unknown key '{key}'"))?;
+                codes.extend_from_slice(named);
+            }
+        }
+    }
+    if codes.is_empty() {
+        return Err("no keys given".to_string());
+    }
+    Ok(codes)
+}
+
+/// Returns a display label for a key code.
+fn key_label(code: i32) -> String {
+    KEY_NAMES
+        .iter()
+        .find(|(_, codes)| codes.contains(&code))
+        .map_or_else(
+            || {
+                u8::try_from(code)
+                    .map_or_else(|_| "?".to_string(), |byte| char::from(byte).to_string())
+            },
+            |(name, _)| (*name).to_string(),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(name, keys)| (name.to_string(), keys.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn overriding_replaces_the_keys_of_an_action() {
+        let keymap = KeyMap::with_overrides(&overrides(&[("quit", "Q Escape")])).unwrap();
+        assert_eq!(
+            keymap.action(i32::from(b'Q')),
+            Some(Action::Navigate(Key::Quit))
+        );
+        assert_eq!(keymap.action(KEY_ESCAPE), Some(Action::Navigate(Key::Quit)));
+        assert_eq!(keymap.action(i32::from(b'q')), None);
+        assert_eq!(keymap.label(Action::Navigate(Key::Quit)), "Q");
+    }
+
+    #[test]
+    fn taking_a_default_key_from_another_action_is_an_error() {
+        let error = KeyMap::with_overrides(&overrides(&[("quit", "a")]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            "key 'a' for 'quit' is already bound to 'annotate'; rebind 'annotate' as well"
+        );
+    }
+
+    #[test]
+    fn keys_can_move_between_rebound_actions() {
+        let keymap =
+            KeyMap::with_overrides(&overrides(&[("quit", "a"), ("annotate", "q")])).unwrap();
+        assert_eq!(
+            keymap.action(i32::from(b'a')),
+            Some(Action::Navigate(Key::Quit))
+        );
+        assert_eq!(keymap.action(i32::from(b'q')), Some(Action::Annotate));
+        assert_eq!(keymap.label(Action::Annotate), "q");
+    }
+
+    #[test]
+    fn giving_one_key_to_two_actions_is_an_error() {
+        let error = KeyMap::with_overrides(&overrides(&[("quit", "Q"), ("annotate", "Q")]))
+            .err()
+            .unwrap();
+        assert_eq!(error, "key 'Q' is bound to both 'quit' and 'annotate'");
+    }
+}
@@ -0,0 +1,141 @@
+/// A single line of text being edited, independent of the terminal backend.
+///
+/// The text is kept as characters rather than bytes, so the cursor, Backspace and Delete
+/// always act on whole characters, however many bytes they take in UTF-8. Input arrives
+/// one byte at a time from the terminal; bytes are held until they form a complete
+/// character.
+#[derive(Debug, Default)]
+pub struct LineEditor {
+    chars: Vec<char>,
+    cursor: usize,    // Index into `chars` where the next character is inserted
+    pending: Vec<u8>, // Bytes of a partially received UTF-8 character
+}
+
+impl LineEditor {
+    /// Starts editing `initial`, with the cursor at the end.
+    pub fn new(initial: &str) -> Self {
+        let chars: Vec<char> = initial.chars().collect();
+        Self {
+            cursor: chars.len(),
+            chars,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the text as a string.
+    pub fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    /// Returns the text before and after the cursor.
+    pub fn split_at_cursor(&self) -> (String, String) {
+        (
+            self.chars[..self.cursor].iter().collect(),
+            self.chars[self.cursor..].iter().collect(),
+        )
+    }
+
+    /// Takes one byte of input, inserting a character at the cursor once it is complete.
+    /// Invalid UTF-8 sequences are dropped.
+    pub fn push_byte(&mut self, byte: u8) {
+        self.pending.push(byte);
+        match std::str::from_utf8(&self.pending) {
+            Ok(decoded) => {
+                for c in decoded.chars() {
+                    self.chars.insert(self.cursor, c);
+                    self.cursor += 1;
+                }
+                self.pending.clear();
+            }
+            Err(e) if e.error_len().is_some() => self.pending.clear(), // Invalid sequence
+            Err(_) => {} // Wait for the rest of the character
+        }
+    }
+
+    /// Removes the character before the cursor.
+    pub fn backspace(&mut self) {
+        self.pending.clear();
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Removes the character under the cursor.
+    pub fn delete(&mut self) {
+        self.pending.clear();
+        if self.cursor < self.chars.len() {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    /// Moves the cursor one character to the left.
+    pub const fn left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Moves the cursor one character to the right.
+    pub fn right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    /// Moves the cursor to the start of the line.
+    pub const fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Moves the cursor to the end of the line.
+    pub const fn end(&mut self) {
+        self.cursor = self.chars.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        text.bytes().for_each(|byte| editor.push_byte(byte));
+        editor
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_multibyte_character() {
+        let mut editor = typed("añ€🦀");
+        assert_eq!(editor.text(), "añ€🦀");
+        editor.backspace();
+        assert_eq!(editor.text(), "añ€");
+        editor.backspace();
+        assert_eq!(editor.text(), "añ");
+        editor.backspace();
+        assert_eq!(editor.text(), "a");
+        editor.backspace();
+        editor.backspace();
+        assert_eq!(editor.text(), "");
+    }
+
+    #[test]
+    fn editing_in_the_middle_of_multibyte_text() {
+        let mut editor = LineEditor::new("日本語");
+        editor.left();
+        editor.backspace();
+        assert_eq!(
+            editor.split_at_cursor(),
+            ("日".to_string(), "語".to_string())
+        );
+        editor.home();
+        editor.delete();
+        assert_eq!(editor.text(), "語");
+    }
+
+    #[test]
+    fn backspace_drops_a_partial_character() {
+        let mut editor = typed("é");
+        editor.push_byte("€".as_bytes()[0]);
+        editor.backspace();
+        assert_eq!(editor.text(), "");
+        editor.push_byte(b'x');
+        assert_eq!(editor.text(), "x");
+    }
+}
@@ -1,29 +1,317 @@
+mod annotations;
+mod app;
+mod columns;
+mod config;
+mod error_list;
+mod format;
+mod html;
+mod keymap;
+mod line_edit;
+mod plain;
+mod positions;
+mod progress;
+mod script;
+mod tsv;
+mod tui;
+
+use app::{AppState, Key};
 use clap::Parser;
-use librsdu::{traverse_directory, FileInfo};
-use ncurses::*;
+use columns::{ColumnKind, DEFAULT_COLUMNS};
+use config::Config;
+use format::SizeStyle;
+use keymap::{Action, KeyMap};
+use librsdu::exclude::ExcludeSet;
+use librsdu::largest::largest_file;
+use librsdu::mounts::filesystem_usage;
+use librsdu::summary::{RepoSize, Summary};
+use librsdu::{
+    percentage, scan, scan_direct_totals, scan_totals_with, scan_with_progress, FileInfo,
+    JsonOptions, PercentOf, Scan, ScanOptions, Xattrs,
+};
+use positions::Positions;
+use progress::{ProgressFormat, ProgressReporter};
+use std::fmt::Write as _;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, IsTerminal, Write};
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Command-line arguments parser.
 #[derive(Parser)]
+#[allow(clippy::struct_excessive_bools)] // Independent command-line switches
 #[command(name = "rsdu", about = "A Rust-based ncdu replacement")]
 struct Cli {
-    #[arg(help = "Directory to scan")]
+    #[arg(help = "Directory to scan, or @name for a user directory such as @downloads or @cache")]
     directory: String,
+
+    #[arg(long, help = "Print only the total item count and exit")]
+    count_only: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CountMode::All,
+        help = "Which entries --count-only counts"
+    )]
+    count_mode: CountMode,
+
+    #[arg(
+        long,
+        help = "Also scan pseudo filesystems such as /proc, /sys and /dev"
+    )]
+    include_pseudo: bool,
+
+    #[arg(
+        long,
+        help = "Use a line-based interface on stdin/stdout instead of ncurses"
+    )]
+    plain: bool,
+
+    #[arg(long, help = "Show only directories in the listing")]
+    dirs_only: bool,
+
+    #[arg(long, help = "Print aggregate statistics as a JSON object and exit")]
+    json_summary: bool,
+
+    #[arg(long, help = "Distinguish upper and lower case when sorting by name")]
+    case_sensitive: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Comma-separated columns to show [default: flag,size,bar,name]"
+    )]
+    columns: Option<Vec<ColumnKind>>,
+
+    #[arg(
+        long,
+        help = "Count entries first so scan progress can show a percentage and time remaining"
+    )]
+    estimate: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "On exit, write a shell script deleting the marked entries to FILE ('-' for stdout)"
+    )]
+    delete_script: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = format::parse_block_size,
+        help = "Count allocated sizes as if the filesystem used SIZE blocks (e.g. 4K)"
+    )]
+    simulate_block_size: Option<NonZeroU64>,
+
+    #[arg(
+        long,
+        help = "Read the size of each entry's extended attributes and show it on the status line"
+    )]
+    xattrs: bool,
+
+    #[arg(
+        long,
+        help = "Like --xattrs, and also add extended attribute sizes to the apparent sizes and totals"
+    )]
+    count_xattrs: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write a self-contained HTML report of the scan to FILE ('-' for stdout) instead of browsing"
+    )]
+    export_html: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "GLOB",
+        help = "Leave out entries matching GLOB: a file name, or the full path if it has a '/' (repeatable)"
+    )]
+    exclude: Vec<String>,
+    #[arg(
+        long,
+        value_name = "REGEX",
+        help = "Leave out entries whose full path matches REGEX anywhere; anchor with ^ and $ (repeatable)"
+    )]
+    exclude_regex: Vec<String>,
+    #[arg(
+        long,
+        help = "Match --exclude and --exclude-regex patterns ignoring case"
+    )]
+    exclude_ignore_case: bool,
+
+    #[arg(
+        long,
+        help = "Write sizes exactly as 'ls -lh' does, e.g. 4.0K or 11K, always rounding up"
+    )]
+    ls_compatible: bool,
+
+    #[arg(long, help = "Allow scanning a filesystem root such as '/'")]
+    allow_root_scan: bool,
+
+    #[arg(
+        long,
+        help = "Write progress to stderr as one JSON record per line, for other programs to read"
+    )]
+    machine_progress: bool,
+
+    #[arg(
+        long,
+        help = "Report how a git repository's size splits between .git and the working tree"
+    )]
+    git_report: bool,
+
+    #[arg(
+        long,
+        help = "Print the size of the directory and its direct entries only, without recursing, and exit"
+    )]
+    no_recursion: bool,
+
+    #[arg(
+        long,
+        value_name = "TYPES",
+        value_delimiter = ',',
+        help = "Only descend into mounts of these filesystem types, e.g. ext4,xfs; the root's filesystem is always scanned"
+    )]
+    fs_types: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "TYPES",
+        value_delimiter = ',',
+        help = "Leave out mounts of these filesystem types, e.g. nfs,tmpfs"
+    )]
+    skip_fs_types: Vec<String>,
+
+    #[arg(
+        short = 'F',
+        long,
+        help = "Append / to directory names in the listing, like ls -F"
+    )]
+    classify: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = format::parse_duration,
+        conflicts_with_all = ["count_only", "no_recursion"],
+        help = "Stop scanning after DURATION (e.g. 30s, 5m) and show what was read so far"
+    )]
+    timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the scanned tree as JSON to FILE (- for stdout) instead of browsing"
+    )]
+    export_json: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        requires = "export_json",
+        help = "Add each entry's share of its parent's or the root's size to --export-json"
+    )]
+    percent_of: Option<PercentBasis>,
+
+    #[arg(
+        long,
+        requires = "percent_of",
+        help = "Leave absolute sizes out of --export-json, keeping only the percentages"
+    )]
+    percent_only: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the scanned tree as folded stacks for flame graph tools to FILE (- for stdout)"
+    )]
+    export_folded: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["plain", "delete_script"],
+        help = "Run unattended: ignore keys that quit, mark, annotate or open a shell, rescan periodically and return to the top directory when idle"
+    )]
+    kiosk: bool,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = format::parse_interval,
+        conflicts_with = "plain",
+        help = "Return to the top directory after DURATION (e.g. 90s) without input [default with --kiosk: 60s]"
+    )]
+    idle_reset: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = format::parse_interval,
+        conflicts_with = "plain",
+        help = "Quit after DURATION (e.g. 8h) without input"
+    )]
+    idle_quit: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = format::parse_interval,
+        conflicts_with = "plain",
+        help = "Rescan every DURATION (e.g. 10m), keeping the open directory [default with --kiosk: 5m]"
+    )]
+    refresh: Option<Duration>,
+
+    #[arg(long, help = "Print the size and path of the largest file and exit")]
+    biggest: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "kiosk",
+        help = "On exit, ask to delete the marked entries, removing symbolic links but never what they point to"
+    )]
+    delete_marked: bool,
+}
+
+/// What the percentages in `--export-json` are relative to.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum PercentBasis {
+    /// The directory containing each entry.
+    Parent,
+    /// The scan root.
+    Root,
 }
 
-/// Holds the application state for navigation.
-struct AppState {
-    stack: Vec<FileInfo>,
-    selected_index: usize,
+/// Which entries are included in an item count.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum CountMode {
+    /// Count files and directories.
+    All,
+    /// Count only non-directory entries.
+    Files,
 }
 
 fn main() {
     // Parse command-line arguments.
     let args = Cli::parse();
 
+    if args.directory.trim().is_empty() {
+        eprintln!("Error: no directory given. Try 'rsdu .' to scan the current directory.");
+        std::process::exit(2);
+    }
+
     // Resolve the absolute path.
-    let root_path = match fs::canonicalize(&args.directory) {
+    let directory = resolve_named_directory(&args.directory).unwrap_or_else(|e| {
+        eprintln!("Error resolving path '{}': {e}", args.directory);
+        std::process::exit(1);
+    });
+    let root_path = match fs::canonicalize(&directory) {
         Ok(path) => path,
         Err(e) => {
             eprintln!("Error resolving path '{}': {}", args.directory, e);
@@ -31,175 +319,522 @@ fn main() {
         }
     };
 
+    // Scanning a whole system is slow and walks into pseudo filesystems, so it must be asked for.
+    if root_path.parent().is_none() && !args.allow_root_scan {
+        eprintln!(
+            "Error: refusing to scan the filesystem root '{}'. Pass --allow-root-scan to scan it anyway.",
+            root_path.display()
+        );
+        std::process::exit(2);
+    }
+
+    let scan_options = scan_options(&args);
+
+    // Print the item count or direct size without building the tree.
+    if args.count_only || args.no_recursion {
+        print_totals(&args, &root_path, &scan_options);
+        return;
+    }
+
     // Traverse the directory and build the file tree.
-    let root_info = match traverse_directory(&root_path) {
-        Ok(info) => info,
-        Err(e) => {
-            eprintln!(
-                "Error traversing directory '{}': {}",
-                root_path.display(),
-                e
-            );
+    let scan_start = Instant::now();
+    let (scan, estimated_items) = run_scan(&args, &root_path, &scan_options);
+
+    if let Some(target) = &args.export_html {
+        print_scan_errors(&scan);
+        write_export(target, &html::report(&scan.root), "HTML report");
+        return;
+    }
+
+    if let Some(target) = &args.export_json {
+        print_scan_errors(&scan);
+        export_json(&args, &scan.root, target);
+        return;
+    }
+
+    if let Some(target) = &args.export_folded {
+        print_scan_errors(&scan);
+        write_export(
+            target,
+            &scan.root.to_folded_stacks(),
+            "folded stacks export",
+        );
+        return;
+    }
+
+    if args.git_report {
+        print_git_report(&scan.root);
+        return;
+    }
+
+    if args.biggest {
+        print_biggest(&args, &scan.root);
+        return;
+    }
+
+    if args.json_summary {
+        print_scan_errors(&scan);
+        let mut summary = Summary::of(&scan.root);
+        summary.scan_duration = Some(scan_start.elapsed());
+        println!("{}", summary.to_json());
+        return;
+    }
+
+    browse(&args, scan, estimated_items, &root_path, &scan_options);
+}
+
+/// Builds the scan options from the command line, exiting if a pattern is invalid.
+fn scan_options(args: &Cli) -> ScanOptions {
+    let exclude = ExcludeSet::new(&args.exclude, &args.exclude_regex, args.exclude_ignore_case)
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {e}");
             std::process::exit(1);
-        }
+        });
+    let mut options = ScanOptions {
+        block_size: args.simulate_block_size,
+        exclude,
+        xattrs: if args.count_xattrs {
+            Xattrs::InTotals
+        } else if args.xattrs {
+            Xattrs::Separate
+        } else {
+            Xattrs::Skip
+        },
+        ..ScanOptions::default()
     };
+    if !args.include_pseudo {
+        options.skip_pseudo_filesystems();
+    }
+    if !args.fs_types.is_empty() || !args.skip_fs_types.is_empty() {
+        let skipped = |fs_type: &str| {
+            let listed = |types: &[String]| types.iter().any(|listed| listed == fs_type);
+            (!args.fs_types.is_empty() && !listed(&args.fs_types)) || listed(&args.skip_fs_types)
+        };
+        if let Err(e) = options.skip_filesystems(skipped) {
+            eprintln!("Error: --fs-types and --skip-fs-types need the mount table: {e}");
+            std::process::exit(1);
+        }
+    }
+    options
+}
+
+/// Reports that the scan root itself could not be read and exits.
+fn exit_unreadable_root(root_path: &Path, error: &io::Error) -> ! {
+    if error.kind() == io::ErrorKind::PermissionDenied {
+        eprintln!(
+            "Error: '{}' cannot be read: permission denied. Nothing was scanned.",
+            root_path.display()
+        );
+    } else {
+        eprintln!(
+            "Error traversing directory '{}': {}",
+            root_path.display(),
+            error
+        );
+    }
+    std::process::exit(1);
+}
+
+/// Prints what is known about a scan root that is a file rather than a directory.
+fn print_file_info(file: &FileInfo, size_style: SizeStyle) {
+    println!("'{}' is a file, not a directory.", file.path.display());
+    println!(
+        "Size: {}   Disk usage: {}",
+        size_style.format(file.size),
+        size_style.format(file.allocated_size)
+    );
+    if let Some(elapsed) = file.modified.and_then(|modified| modified.elapsed().ok()) {
+        println!("Modified: {} ago", format::age(elapsed));
+    }
+}
 
-    let total_size = root_info.size;
-    let total_items = root_info.items;
+/// Asks on stdin whether to delete the marked entries, then deletes them, reporting any
+/// that could not be removed.
+fn delete_marked(app_state: &AppState) {
+    if app_state.marked.is_empty() {
+        return;
+    }
+    let total = app_state.marked.values().sum();
+    print!(
+        "Delete {} marked entries ({} on disk)? [y/N] ",
+        app_state.marked.len(),
+        format::human_readable_size(total)
+    );
+    let mut answer = String::new();
+    if io::stdout().flush().is_err()
+        || io::stdin().read_line(&mut answer).is_err()
+        || !answer.trim().eq_ignore_ascii_case("y")
+    {
+        return;
+    }
+    let failures = script::delete_marked(&app_state.marked);
+    for (path, e) in &failures {
+        eprintln!("Error deleting '{}': {e}", path.display());
+    }
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+}
 
-    let mut app_state = AppState {
-        stack: vec![root_info],
-        selected_index: 0,
+/// Prints the size and path of the largest file below `root`, separated by a tab, or exits
+/// with an error if there are no files.
+fn print_biggest(args: &Cli, root: &FileInfo) {
+    let Some(file) = largest_file(root) else {
+        eprintln!("No files under '{}'.", root.path.display());
+        std::process::exit(1);
+    };
+    let size_style = if args.ls_compatible {
+        SizeStyle::Ls
+    } else {
+        SizeStyle::Binary
     };
+    println!("{}\t{}", size_style.format(file.size), file.path.display());
+}
 
-    // Initialize ncurses.
-    initscr();
-    keypad(stdscr(), true);
-    noecho();
-    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
-
-    loop {
-        // Clear the screen and get the current directory info.
-        clear();
-        let current_dir = app_state.stack.last().unwrap();
-        let entries = current_dir.children.as_deref().unwrap_or(&[]);
-
-        // Get the window size.
-        let (max_y, max_x) = {
-            let mut y = 0;
-            let mut x = 0;
-            getmaxyx(stdscr(), &mut y, &mut x);
-            (y, x)
+/// Opens the scan in the TUI or plain browser, then writes the delete script if requested.
+///
+/// A root that is a file has nothing to browse, so its details are printed instead.
+fn browse(
+    args: &Cli,
+    scan: Scan,
+    estimated_items: Option<u64>,
+    root_path: &Path,
+    scan_options: &ScanOptions,
+) {
+    if !scan.root.is_dir {
+        let size_style = if args.ls_compatible {
+            SizeStyle::Ls
+        } else {
+            SizeStyle::Binary
         };
+        print_file_info(&scan.root, size_style);
+        return;
+    }
+    let total_items = scan.root.items;
 
-        // Display the header line with the current directory path.
-        let header = format!(
-            "--- {} {}",
-            current_dir.path.display(),
-            "-".repeat((max_x as usize).saturating_sub(current_dir.path.display().to_string().len() + 4))
-        );
-        mvprintw(0, 0, &header);
-
-        // Find the maximum size among entries for bar graph scaling.
-        let max_entry_size = entries.iter().map(|e| e.size).max().unwrap_or(1);
-
-        // Display the list of files and directories.
-        for (i, entry) in entries.iter().enumerate() {
-            if i >= (max_y as usize - 4) {
-                break; // Avoid writing beyond the screen
-            }
-
-            let y_pos = i as i32 + 1;
-            if i == app_state.selected_index {
-                attron(A_REVERSE());
-            }
-
-            let size_str = human_readable_size(entry.size);
-            let bar = generate_bar(entry.size, max_entry_size, 30); // 30 characters wide bar
-
-            let name = entry
-                .path
-                .file_name()
-                .unwrap_or_else(|| entry.path.as_os_str())
-                .to_string_lossy();
-
-            mvprintw(
-                y_pos,
-                0,
-                &format!("{:>10} [{}] {}", size_str, bar, name),
+    let mut app_state = AppState::new(scan.root);
+    app_state.scan_errors = scan.errors;
+    if scan.incomplete {
+        let mut note = String::from("Incomplete: scan timed out");
+        if let Some(estimate) = estimated_items {
+            let _ = write!(
+                note,
+                ", ~{:.0}% of entries read",
+                percentage(total_items, estimate)
             );
+        }
+        app_state.incomplete = Some(note);
+    }
+    app_state.fs_usage = filesystem_usage(root_path).ok();
+    app_state.dirs_only = args.dirs_only;
+    app_state.case_sensitive_sort = args.case_sensitive;
+    app_state.classify = args.classify;
+    if args.ls_compatible {
+        app_state.size_style = SizeStyle::Ls;
+    }
+    match annotations::Annotations::load() {
+        Ok(annotations) => app_state.annotations = annotations,
+        Err(e) => eprintln!("Warning: Could not load annotations: {e}"),
+    }
+    let mut positions = Positions::load().unwrap_or_else(|e| {
+        eprintln!("Warning: Could not load saved positions: {e}");
+        Positions::default()
+    });
+    app_state.idle_reset = args
+        .idle_reset
+        .or_else(|| args.kiosk.then_some(KIOSK_IDLE_RESET));
+    app_state.idle_quit = args.idle_quit;
+    app_state.refresh = args.refresh.or_else(|| args.kiosk.then_some(KIOSK_REFRESH));
+    if !args.kiosk {
+        app_state.resume_at = positions.get(root_path).map(Path::to_path_buf);
+    }
+
+    let columns: Vec<_> = args
+        .columns
+        .as_deref()
+        .unwrap_or(DEFAULT_COLUMNS)
+        .iter()
+        .map(|kind| kind.column())
+        .collect();
 
-            if i == app_state.selected_index {
-                attroff(A_REVERSE());
-            }
+    if args.plain {
+        if let Err(e) = plain::run(&mut app_state, &columns) {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
         }
+    } else {
+        let keymap = Config::load()
+            .map_err(|e| e.to_string())
+            .and_then(|config| KeyMap::with_overrides(&config.keys))
+            .unwrap_or_else(|e| {
+                eprintln!("Error in configuration: {e}");
+                std::process::exit(1);
+            });
+        let keymap = if args.kiosk {
+            keymap.without(KIOSK_DISABLED_ACTIONS)
+        } else {
+            keymap
+        };
+        let rescan = || librsdu::scan(root_path, &with_timeout(scan_options, args.timeout));
+        tui::run(&mut app_state, &columns, &keymap, &rescan);
+    }
 
-        // Display the footer with total disk usage, apparent size, and items.
-        let footer_y = (max_y - 2) as i32;
-        let total_size_str = human_readable_size(total_size);
-        mvprintw(
-            footer_y,
-            0,
-            &format!(
-                "*Total disk usage: {:>10}   Apparent size: {:>10}   Items: {}",
-                total_size_str, total_size_str, total_items
-            ),
-        );
+    if !args.kiosk {
+        if let Err(e) = positions.set(root_path, &app_state.current_dir().path) {
+            eprintln!("Warning: Could not save position: {e}");
+        }
+    }
+
+    if let Some(target) = &args.delete_script {
+        if let Err(e) = write_delete_script(target, &app_state) {
+            eprintln!("Error writing delete script '{}': {e}", target.display());
+            std::process::exit(1);
+        }
+    }
+
+    if args.delete_marked {
+        delete_marked(&app_state);
+    }
+}
 
-        // Display instructions.
-        mvprintw(
-            (max_y - 1) as i32,
-            0,
-            "Press 'q' to quit. Use arrow keys to navigate. Enter to open directory. Backspace to go back.",
+/// Prints how the repository at `root` splits between `.git` and the working tree.
+///
+/// Exits with an error if `root` is not the top of a git repository.
+fn print_git_report(root: &FileInfo) {
+    let Some(repo) = RepoSize::of(root) else {
+        eprintln!(
+            "Error: '{}' has no .git directory; run --git-report from the top of a repository.",
+            root.path.display()
         );
+        std::process::exit(1);
+    };
+    let ratio = if repo.working_tree == 0 {
+        String::new()
+    } else {
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = repo.git_dir as f64 / repo.working_tree as f64;
+        format!(", {ratio:.2}x the working tree")
+    };
+    println!(
+        "Git history (.git): {}   Working tree: {}   History is {:.1}% of the repository{ratio}",
+        format::human_readable_size(repo.git_dir),
+        format::human_readable_size(repo.working_tree),
+        repo.git_percentage()
+    );
+}
 
-        refresh();
-
-        // Handle user input.
-        let ch = getch();
-        match ch {
-            KEY_UP => {
-                if app_state.selected_index > 0 {
-                    app_state.selected_index -= 1;
-                }
-            }
-            KEY_DOWN => {
-                if app_state.selected_index + 1 < entries.len() {
-                    app_state.selected_index += 1;
-                }
-            }
-            10 => {
-                // Enter key to navigate into a directory.
-                let selected_entry = &entries[app_state.selected_index];
-                if selected_entry.is_dir {
-                    app_state.stack.push(selected_entry.clone());
-                    app_state.selected_index = 0;
-                }
-            }
-            ch if ch == 'q' as i32 => {
-                // Quit the application.
-                break;
-            }
-            KEY_BACKSPACE | 127 | 8 => {
-                // Backspace to go up one directory.
-                if app_state.stack.len() > 1 {
-                    app_state.stack.pop();
-                    app_state.selected_index = 0;
-                }
-            }
-            _ => {}
+/// Prints the totals asked for by `--count-only` or `--no-recursion`, without building a tree.
+fn print_totals(args: &Cli, root_path: &Path, scan_options: &ScanOptions) {
+    let totals = if args.no_recursion {
+        scan_direct_totals(root_path, scan_options)
+    } else {
+        scan_totals_with(root_path, scan_options)
+    };
+    match totals {
+        Ok(totals) if args.count_only => match args.count_mode {
+            CountMode::All => println!("{}", totals.items),
+            CountMode::Files => println!("{}", totals.files),
+        },
+        Ok(totals) => println!(
+            "Disk usage: {}   Apparent size: {}   Items: {}   (direct entries only)",
+            format::human_readable_size(totals.allocated_size),
+            format::human_readable_size(totals.size),
+            totals.items
+        ),
+        Err(e) => {
+            eprintln!(
+                "Error traversing directory '{}': {}",
+                root_path.display(),
+                e
+            );
+            std::process::exit(1);
         }
     }
+}
+
+/// Writes a script deleting the marked entries to `target`, or to stdout if it is `-`.
+///
+/// Nothing is written when no entries were marked.
+fn write_delete_script(target: &Path, app_state: &AppState) -> io::Result<()> {
+    if app_state.marked.is_empty() {
+        return Ok(());
+    }
+    let script = script::delete_script(&app_state.marked);
+    if target == Path::new("-") {
+        io::stdout().write_all(&script)
+    } else {
+        fs::write(target, script)?;
+        eprintln!(
+            "Wrote the delete script for {} marked entries to '{}'.",
+            app_state.marked.len(),
+            target.display()
+        );
+        Ok(())
+    }
+}
 
-    // End ncurses mode.
-    endwin();
+/// Writes the tree as JSON to `target`, or to stdout if it is `-`.
+fn export_json(args: &Cli, root: &FileInfo, target: &Path) {
+    let options = JsonOptions {
+        percent_of: args.percent_of.map(|basis| match basis {
+            PercentBasis::Parent => PercentOf::Parent,
+            PercentBasis::Root => PercentOf::Root,
+        }),
+        percent_only: args.percent_only,
+    };
+    write_export(target, &(root.to_json_with(options) + "\n"), "JSON export");
+}
+
+/// Reports the entries the scan could not read on stderr, for modes without the browser's
+/// error list.
+fn print_scan_errors(scan: &Scan) {
+    for error in &scan.errors {
+        eprintln!("Warning: Could not traverse {error}");
+    }
 }
 
-fn generate_bar(size: u64, max_size: u64, bar_width: usize) -> String {
-    let ratio = size as f64 / max_size as f64;
-    let filled_length = (ratio * bar_width as f64).round() as usize;
-    let bar = "#".repeat(filled_length);
-    let empty = " ".repeat(bar_width - filled_length);
-    format!("{}{}", bar, empty)
+/// Writes `contents` to `target`, or to stdout if it is `-`, and exits on failure.
+fn write_export(target: &Path, contents: &str, description: &str) {
+    let result = if target == Path::new("-") {
+        io::stdout().write_all(contents.as_bytes())
+    } else {
+        fs::write(target, contents)
+    };
+    if let Err(e) = result {
+        eprintln!("Error writing {description} '{}': {e}", target.display());
+        std::process::exit(1);
+    }
 }
 
-fn human_readable_size(size: u64) -> String {
-    const KB: f64 = 1024.0;
-    const MB: f64 = KB * 1024.0;
-    const GB: f64 = MB * 1024.0;
-    const TB: f64 = GB * 1024.0;
+/// How long `--kiosk` waits without input before returning to the top directory, unless
+/// `--idle-reset` says otherwise.
+const KIOSK_IDLE_RESET: Duration = Duration::from_mins(1);
+
+/// How often `--kiosk` rescans, unless `--refresh` says otherwise.
+const KIOSK_REFRESH: Duration = Duration::from_mins(5);
+
+/// Actions that change files or leave rsdu, which `--kiosk` ignores.
+const KIOSK_DISABLED_ACTIONS: &[Action] = &[
+    Action::Navigate(Key::Quit),
+    Action::Navigate(Key::ToggleMark),
+    Action::Annotate,
+    Action::Shell,
+];
 
-    let size_f = size as f64;
+/// Looks up one of the user's well-known directories.
+type DirectoryLookup = fn() -> Option<PathBuf>;
 
-    if size_f >= TB {
-        format!("{:.1} TiB", size_f / TB)
-    } else if size_f >= GB {
-        format!("{:.1} GiB", size_f / GB)
-    } else if size_f >= MB {
-        format!("{:.1} MiB", size_f / MB)
-    } else if size_f >= KB {
-        format!("{:.1} KiB", size_f / KB)
+/// Well-known directories accepted as `@name` in place of a path.
+const NAMED_DIRECTORIES: &[(&str, DirectoryLookup)] = &[
+    ("home", dirs::home_dir),
+    ("desktop", dirs::desktop_dir),
+    ("documents", dirs::document_dir),
+    ("downloads", dirs::download_dir),
+    ("music", dirs::audio_dir),
+    ("pictures", dirs::picture_dir),
+    ("videos", dirs::video_dir),
+    ("public", dirs::public_dir),
+    ("templates", dirs::template_dir),
+    ("cache", dirs::cache_dir),
+    ("config", dirs::config_dir),
+    ("data", dirs::data_dir),
+];
+
+/// Expands `@downloads` and the other names in [`NAMED_DIRECTORIES`] to the user's directory.
+///
+/// Anything else, including unknown `@` names, is returned as a literal path.
+fn resolve_named_directory(argument: &str) -> Result<PathBuf, String> {
+    let Some(name) = argument.strip_prefix('@') else {
+        return Ok(PathBuf::from(argument));
+    };
+    match NAMED_DIRECTORIES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+    {
+        Some((known, lookup)) => {
+            lookup().ok_or_else(|| format!("no {known} directory is set up for this user"))
+        }
+        None => Ok(PathBuf::from(argument)),
+    }
+}
+
+/// Scans `root_path`, reporting progress on stderr when asked to or when it is a terminal,
+/// and exits if the root cannot be read.
+///
+/// With `--estimate`, the entries are counted first; the count is returned alongside the
+/// scan. One `--timeout` deadline covers both the counting pass and the scan, and a count
+/// cut short by it is thrown away rather than returned.
+fn run_scan(args: &Cli, root_path: &Path, scan_options: &ScanOptions) -> (Scan, Option<u64>) {
+    let format = if args.machine_progress {
+        Some(ProgressFormat::Machine)
+    } else if !args.json_summary && io::stderr().is_terminal() {
+        Some(ProgressFormat::Human)
+    } else {
+        None
+    };
+
+    // One deadline covers the estimate and the scan.
+    let scan_options = with_timeout(scan_options, args.timeout);
+    let estimated_items = if args.estimate && (format.is_some() || args.timeout.is_some()) {
+        if format == Some(ProgressFormat::Human) {
+            eprint!("Estimating...");
+        }
+        let totals = scan_totals_with(root_path, &scan_options).ok();
+        if format == Some(ProgressFormat::Human) {
+            eprint!("\r\x1b[K");
+        }
+        // A count cut short by the timeout is too low to estimate progress with.
+        totals
+            .filter(|_| !is_cancelled(&scan_options))
+            .map(|totals| totals.items)
     } else {
-        format!("{} B", size)
+        None
+    };
+
+    let result = format.map_or_else(
+        || scan(root_path, &scan_options),
+        |format| scan_with_reporter(root_path, &scan_options, format, estimated_items),
+    );
+    let scan = result.unwrap_or_else(|e| exit_unreadable_root(root_path, &e));
+    if scan.incomplete {
+        eprintln!("Warning: the scan timed out; sizes and counts are partial.");
+    }
+    (scan, estimated_items)
+}
+
+/// Returns `true` if the scan under `options` has been cancelled.
+fn is_cancelled(options: &ScanOptions) -> bool {
+    options
+        .cancel
+        .as_ref()
+        .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+}
+
+/// Returns a copy of `options` that cancels the scan once `timeout` has passed from now.
+fn with_timeout(options: &ScanOptions, timeout: Option<Duration>) -> ScanOptions {
+    let mut options = options.clone();
+    if let Some(timeout) = timeout {
+        let cancel = Arc::new(AtomicBool::new(false));
+        options.cancel = Some(Arc::clone(&cancel));
+        thread::spawn(move || {
+            thread::sleep(timeout);
+            cancel.store(true, Ordering::Relaxed);
+        });
     }
+    options
+}
+
+/// Scans `root_path` while reporting progress on stderr, with a percentage if the total is known.
+fn scan_with_reporter(
+    root_path: &Path,
+    scan_options: &ScanOptions,
+    format: ProgressFormat,
+    estimated_total: Option<u64>,
+) -> io::Result<Scan> {
+    let mut reporter = ProgressReporter::new(format, estimated_total);
+    let result = scan_with_progress(root_path, scan_options, &mut |progress| {
+        reporter.update(progress);
+    });
+    reporter.finish();
+    result
 }
@@ -0,0 +1,193 @@
+use crate::app::AppState;
+use crate::columns::{fitted_widths, render_header, render_row, Column, RowContext};
+use crate::error_list::{error_line, ErrorList};
+use std::io::{self, BufRead, Write};
+
+/// Runs a line-based browser on stdin/stdout, for terminals where ncurses is unavailable.
+///
+/// Each round prints a numbered listing and reads one command: an entry number to open it,
+/// `m` and a number to mark or unmark it for the delete script, `e` to list the scan errors
+/// (optionally followed by text to filter them), `u` to go up a directory, or `q` to quit.
+/// End of input also quits.
+pub fn run(app_state: &mut AppState, columns: &[Column]) -> io::Result<()> {
+    run_with(
+        &mut io::stdin().lock(),
+        &mut io::stdout().lock(),
+        app_state,
+        columns,
+    )
+}
+
+/// Like [`run`], reading commands from `input` and writing to `stdout`.
+fn run_with(
+    input: &mut impl BufRead,
+    stdout: &mut impl Write,
+    app_state: &mut AppState,
+    columns: &[Column],
+) -> io::Result<()> {
+    let mut line = String::new();
+
+    if let Some(path) = app_state.resume_at.take() {
+        write!(stdout, "Resume at {}? [y/N] ", path.display())?;
+        stdout.flush()?;
+        input.read_line(&mut line)?;
+        if line.trim().eq_ignore_ascii_case("y") && !app_state.open(&path) {
+            writeln!(
+                stdout,
+                "The saved location is no longer in the scanned tree."
+            )?;
+        }
+    }
+
+    loop {
+        app_state.clamp_selection();
+        print_listing(stdout, app_state, columns)?;
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        // Commands are a word, then any argument after whitespace.
+        let command = line.trim();
+        let (word, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(word, argument)| (word, argument.trim()));
+        match (word, argument) {
+            ("q", "") => return Ok(()),
+            ("u", "") => {
+                if !app_state.go_back() {
+                    writeln!(stdout, "Already at the top directory.")?;
+                }
+            }
+            ("", _) => {}
+            ("e", filter) => print_errors(stdout, app_state, filter)?,
+            ("m", number) => match number.parse::<usize>() {
+                Ok(number) if (1..=app_state.entries().len()).contains(&number) => {
+                    app_state.selected_index = number - 1;
+                    app_state.toggle_mark();
+                }
+                _ => writeln!(stdout, "Usage: m <entry number>")?,
+            },
+            _ => match command.parse::<usize>() {
+                Ok(number) if (1..=app_state.entries().len()).contains(&number) => {
+                    if !app_state.enter(number - 1) {
+                        writeln!(stdout, "Entry {number} is not a directory.")?;
+                    }
+                }
+                Ok(number) => writeln!(stdout, "There is no entry {number}.")?,
+                Err(_) => writeln!(stdout, "Unknown command '{command}'.")?,
+            },
+        }
+    }
+}
+
+/// Prints the scan errors matching `filter`, grouped by kind.
+fn print_errors(out: &mut impl Write, app_state: &AppState, filter: &str) -> io::Result<()> {
+    if app_state.scan_errors.is_empty() {
+        return writeln!(out, "No errors during the scan.");
+    }
+    let mut list = ErrorList::new(&app_state.scan_errors);
+    list.set_filter(filter.to_string());
+    writeln!(out, "{}", list.summary())?;
+    for error in list.visible() {
+        writeln!(out, "{}", error_line(error))?;
+    }
+    Ok(())
+}
+
+/// Prints the current directory, its numbered entries and the totals.
+fn print_listing(out: &mut impl Write, app_state: &AppState, columns: &[Column]) -> io::Result<()> {
+    let entries = app_state.entries();
+    let context = RowContext {
+        max_size: entries
+            .iter()
+            .map(|e| app_state.metric.value(e))
+            .max()
+            .unwrap_or(1),
+        dir_size: app_state.metric.whole(app_state.current_dir()),
+        annotations: &app_state.annotations,
+        marked: &app_state.marked,
+        size_style: app_state.size_style,
+        metric: app_state.metric,
+        classify: app_state.classify,
+        duplicates: app_state.duplicates.as_ref(),
+    };
+
+    let widths = fitted_widths(columns, &entries, &context);
+
+    writeln!(out, "--- {}", app_state.current_dir().path.display())?;
+    writeln!(out, "{:>4}  {}", "#", render_header(columns, &widths))?;
+    if let Some(message) = app_state.empty_listing_message() {
+        writeln!(out, "      {message}")?;
+    }
+    for (number, entry) in entries.iter().enumerate().map(|(i, e)| (i + 1, e)) {
+        writeln!(
+            out,
+            "{number:>4}. {}",
+            render_row(columns, &widths, entry, &context)
+        )?;
+    }
+    let root = &app_state.stack[0];
+    writeln!(
+        out,
+        "Disk usage: {}   Apparent size: {}   Items: {}",
+        app_state.size_style.format(root.allocated_size),
+        app_state.size_style.format(root.size),
+        root.contained_items()
+    )?;
+    if let Some(errors) = app_state.error_summary() {
+        writeln!(out, "{errors}")?;
+    }
+    if let Some(note) = &app_state.incomplete {
+        writeln!(out, "{note}")?;
+    }
+    writeln!(
+        out,
+        "Enter a number to open a directory, 'm <number>' to mark, 'e' for errors, 'u' to go up, 'q' to quit."
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::columns::ColumnKind;
+    use librsdu::FileInfo;
+    use std::path::Path;
+
+    /// Runs the plain browser over a small tree with `input` as stdin, returning the output.
+    fn session(app_state: &mut AppState, input: &str) -> String {
+        let columns = [ColumnKind::Name.column()];
+        let mut output = Vec::new();
+        run_with(&mut input.as_bytes(), &mut output, app_state, &columns).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn tree() -> FileInfo {
+        let mut root = FileInfo::new_dir("/root".into(), 0);
+        root.add_child(FileInfo::new("/root/file".into(), 10, false, 1));
+        root
+    }
+
+    #[test]
+    fn words_starting_with_a_command_letter_are_unknown_commands() {
+        let mut app_state = AppState::new(tree());
+        let output = session(&mut app_state, "exit\nmore\nm1\nqq\n");
+        assert!(output.contains("Unknown command 'exit'."));
+        assert!(output.contains("Unknown command 'more'."));
+        assert!(output.contains("Unknown command 'm1'."));
+        assert!(output.contains("Unknown command 'qq'."));
+        assert!(app_state.marked.is_empty());
+    }
+
+    #[test]
+    fn command_letters_take_an_argument_after_whitespace() {
+        let mut app_state = AppState::new(tree());
+        let output = session(&mut app_state, "m 1\ne\nm\nq\n");
+        assert!(app_state.marked.contains_key(Path::new("/root/file")));
+        assert!(output.contains("Usage: m <entry number>"));
+        assert!(!output.contains("Unknown command"));
+    }
+}
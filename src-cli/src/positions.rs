@@ -0,0 +1,93 @@
+use crate::tsv::{escape_field, unescape_field};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File name of the positions store inside the rsdu state directory.
+const STORE_FILE: &str = "positions.tsv";
+
+/// The directory last viewed for each scan root, so a later session can resume there.
+///
+/// The store is a tab-separated file of `root<TAB>directory` lines in the XDG state
+/// directory (`~/.local/state/rsdu/positions.tsv` on Linux), escaped like the annotations.
+#[derive(Debug, Default)]
+pub struct Positions {
+    last_viewed: HashMap<PathBuf, PathBuf>,
+    store: Option<PathBuf>,
+}
+
+impl Positions {
+    /// Loads the positions store from the default location.
+    ///
+    /// A missing store is not an error and yields no saved positions.
+    pub fn load() -> io::Result<Self> {
+        let store = dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|dir| dir.join("rsdu").join(STORE_FILE));
+        let last_viewed = match &store {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(contents) => contents
+                    .lines()
+                    .filter_map(|line| line.split_once('\t'))
+                    .map(|(root, dir)| {
+                        (
+                            PathBuf::from(unescape_field(root)),
+                            PathBuf::from(unescape_field(dir)),
+                        )
+                    })
+                    .collect(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+                Err(e) => return Err(e),
+            },
+            None => HashMap::new(),
+        };
+        Ok(Self { last_viewed, store })
+    }
+
+    /// Returns the directory last viewed below `root`, if one was saved.
+    pub fn get(&self, root: &Path) -> Option<&Path> {
+        self.last_viewed.get(root).map(PathBuf::as_path)
+    }
+
+    /// Records `dir` as the last directory viewed below `root` and saves. Viewing the root
+    /// itself forgets the position.
+    pub fn set(&mut self, root: &Path, dir: &Path) -> io::Result<()> {
+        if dir == root {
+            if self.last_viewed.remove(root).is_none() {
+                return Ok(()); // Nothing changed
+            }
+        } else {
+            self.last_viewed
+                .insert(root.to_path_buf(), dir.to_path_buf());
+        }
+        self.save()
+    }
+
+    /// Writes all positions back to the store.
+    fn save(&self) -> io::Result<()> {
+        let Some(store) = &self.store else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no state directory available to store positions",
+            ));
+        };
+        if let Some(parent) = store.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut lines: Vec<String> = self
+            .last_viewed
+            .iter()
+            .map(|(root, dir)| {
+                format!(
+                    "{}\t{}\n",
+                    escape_field(&root.to_string_lossy()),
+                    escape_field(&dir.to_string_lossy())
+                )
+            })
+            .collect();
+        lines.sort();
+        fs::write(store, lines.concat())
+    }
+}
@@ -0,0 +1,169 @@
+use librsdu::Progress;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Minimum time between two progress lines.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Scans that finish sooner than this never show a time estimate.
+const ETA_DELAY: Duration = Duration::from_secs(1);
+
+/// Weight of the newest rate sample in the moving average.
+const SMOOTHING: f64 = 0.2;
+
+/// Longest path suffix shown on the progress line.
+const MAX_PATH_CHARS: usize = 50;
+
+/// How progress is written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// A single, continually rewritten line for people.
+    Human,
+    /// One JSON record per line for other programs, as described in the README.
+    Machine,
+}
+
+/// Reports scan progress on stderr, at most every [`UPDATE_INTERVAL`].
+pub struct ProgressReporter {
+    format: ProgressFormat,
+    start: Instant,
+    last_update: Instant,
+    last_items: u64,
+    items: u64,        // Latest count, including updates that were not written
+    rate: Option<f64>, // Smoothed items per second
+    estimated_total: Option<u64>,
+    drawn: bool,
+}
+
+impl ProgressReporter {
+    /// Creates a reporter. With `estimated_total`, it also reports a percentage and ETA.
+    pub fn new(format: ProgressFormat, estimated_total: Option<u64>) -> Self {
+        let now = Instant::now();
+        Self {
+            format,
+            start: now,
+            last_update: now,
+            last_items: 0,
+            items: 0,
+            rate: None,
+            estimated_total,
+            drawn: false,
+        }
+    }
+
+    /// Records `progress` and reports it if enough time has passed.
+    pub fn update(&mut self, progress: &Progress<'_>) {
+        self.items = progress.items;
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_update);
+        if elapsed < UPDATE_INTERVAL {
+            return;
+        }
+
+        #[allow(clippy::cast_precision_loss)] // Item counts stay far below 2^52
+        let sample = (progress.items - self.last_items) as f64 / elapsed.as_secs_f64();
+        self.rate = Some(self.rate.map_or(sample, |rate| {
+            rate.mul_add(1.0 - SMOOTHING, sample * SMOOTHING)
+        }));
+        self.last_update = now;
+        self.last_items = progress.items;
+
+        if self.format == ProgressFormat::Machine {
+            self.write_record(progress);
+            return;
+        }
+
+        let mut line = format!("Scanning: {} items", progress.items);
+        if let Some(total) = self.estimated_total {
+            let _ = write!(
+                line,
+                " ({:.0}%)",
+                librsdu::percentage(progress.items, total)
+            );
+            if let Some(eta) = self.eta(progress.items, total) {
+                let _ = write!(line, ", {} remaining", format_duration(eta));
+            }
+        }
+        line.push_str("  ");
+        line.push_str(&path_tail(&progress.path.display().to_string()));
+
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[K{line}");
+        let _ = stderr.flush();
+        self.drawn = true;
+    }
+
+    /// Writes a `progress` record for `progress`.
+    fn write_record(&self, progress: &Progress<'_>) {
+        let (percent, eta) = self.estimated_total.map_or((None, None), |total| {
+            (
+                Some(librsdu::percentage(progress.items, total)),
+                self.eta(progress.items, total),
+            )
+        });
+        let record = format!(
+            "{{\"event\":\"progress\",\"items\":{},\"path\":{},\"percent\":{},\"eta_secs\":{}}}",
+            progress.items,
+            librsdu::json::string(&progress.path.to_string_lossy()),
+            percent.map_or_else(|| "null".to_string(), |percent| format!("{percent:.1}")),
+            eta.map_or_else(|| "null".to_string(), |eta| eta.as_secs().to_string()),
+        );
+        let mut stderr = io::stderr().lock();
+        let _ = writeln!(stderr, "{record}");
+    }
+
+    /// Ends the report: clears the progress line, or writes the final `done` record.
+    pub fn finish(self) {
+        if self.format == ProgressFormat::Machine {
+            let _ = writeln!(
+                io::stderr().lock(),
+                "{{\"event\":\"done\",\"items\":{},\"elapsed_secs\":{:.3}}}",
+                self.items,
+                self.start.elapsed().as_secs_f64()
+            );
+            return;
+        }
+        if !self.drawn {
+            return;
+        }
+        let mut stderr = io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[K");
+        let _ = stderr.flush();
+    }
+
+    /// Estimates the time left from the smoothed rate, once the scan has run long enough.
+    fn eta(&self, items: u64, total: u64) -> Option<Duration> {
+        if self.start.elapsed() < ETA_DELAY || items >= total {
+            return None;
+        }
+        let rate = self.rate.filter(|rate| *rate > 0.0)?;
+        #[allow(clippy::cast_precision_loss)]
+        let remaining = (total - items) as f64 / rate;
+        Duration::try_from_secs_f64(remaining).ok()
+    }
+}
+
+/// Formats a duration as `<1s`, `~12s`, `~3m 05s` or `~1h 02m`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs == 0 {
+        "<1s".to_string()
+    } else if secs < 60 {
+        format!("~{secs}s")
+    } else if secs < 3600 {
+        format!("~{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("~{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+/// Returns the last `MAX_PATH_CHARS` characters of `path`, prefixed with `...` if cut.
+fn path_tail(path: &str) -> String {
+    let count = path.chars().count();
+    if count <= MAX_PATH_CHARS {
+        return path.to_string();
+    }
+    let tail: String = path.chars().skip(count - MAX_PATH_CHARS + 3).collect();
+    format!("...{tail}")
+}
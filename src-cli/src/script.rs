@@ -0,0 +1,235 @@
+use crate::format::human_readable_size;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Builds a shell script that deletes the `marked` paths, for the user to review and run.
+///
+/// `marked` maps each path to its size on disk. Paths inside another marked path are left
+/// out, since deleting the parent removes them. Symbolic links are removed with a command
+/// that never recurses, so a link to a directory is deleted without touching what it points
+/// to. On Unix the script is for `sh`; elsewhere it is for PowerShell.
+pub fn delete_script(marked: &BTreeMap<PathBuf, u64>) -> Vec<u8> {
+    let kept = outermost(marked);
+    let total: u64 = kept.iter().map(|&(_, size)| size).sum();
+
+    let mut script = Vec::new();
+    script.extend_from_slice(HEADER.as_bytes());
+    script.extend_from_slice(
+        format!(
+            "# Generated by rsdu: {} entries, {} on disk. Review before running.\n",
+            kept.len(),
+            human_readable_size(total)
+        )
+        .as_bytes(),
+    );
+    for (path, size) in kept {
+        let is_link = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_symlink());
+        let (remove, after) = if is_link { REMOVE_LINK } else { REMOVE };
+        let note = if is_link {
+            " (symbolic link; only the link is removed)"
+        } else {
+            ""
+        };
+        script.extend_from_slice(format!("\n# {}{note}\n", human_readable_size(size)).as_bytes());
+        script.extend_from_slice(remove.as_bytes());
+        script.extend_from_slice(&quote(path));
+        script.extend_from_slice(after.as_bytes());
+        script.push(b'\n');
+    }
+    script
+}
+
+/// Deletes the `marked` paths, returning the ones that could not be removed.
+///
+/// Paths inside another marked path are skipped, as in [`delete_script`]. Each path is
+/// removed with [`remove_entry`], so marked symbolic links never lead outside the tree.
+pub fn delete_marked(marked: &BTreeMap<PathBuf, u64>) -> Vec<(PathBuf, io::Error)> {
+    outermost(marked)
+        .into_iter()
+        .filter_map(|(path, _)| remove_entry(path).err().map(|e| (path.clone(), e)))
+        .collect()
+}
+
+/// Removes the file, directory tree or symbolic link at `path`.
+///
+/// The entry is examined with `symlink_metadata`, so a symbolic link is removed as the link
+/// itself with `remove_file` and whatever it points to, even a directory, is left alone.
+/// Only a real directory is removed recursively.
+pub fn remove_entry(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Returns the `marked` paths that are not inside another marked path, with their sizes.
+fn outermost(marked: &BTreeMap<PathBuf, u64>) -> Vec<(&PathBuf, u64)> {
+    // Paths sort component by component, so a directory comes before its contents.
+    let mut kept: Vec<(&PathBuf, u64)> = Vec::new();
+    for (path, &size) in marked {
+        if !kept.iter().any(|(parent, _)| path.starts_with(parent)) {
+            kept.push((path, size));
+        }
+    }
+    kept
+}
+
+#[cfg(unix)]
+const HEADER: &str = "#!/bin/sh\nset -e\n";
+/// Text before and after the quoted path in the command that removes a path and everything
+/// below it.
+#[cfg(unix)]
+const REMOVE: (&str, &str) = ("rm -rf -- ", "");
+/// Text before and after the quoted path in the command that removes a symbolic link.
+///
+/// Without `-r`, `rm` removes a link to a directory as the link, never its target.
+#[cfg(unix)]
+const REMOVE_LINK: (&str, &str) = ("rm -f -- ", "");
+
+#[cfg(not(unix))]
+const HEADER: &str = "$ErrorActionPreference = 'Stop'\n";
+#[cfg(not(unix))]
+const REMOVE: (&str, &str) = ("Remove-Item -Recurse -Force -LiteralPath ", "");
+/// `Remove-Item -Recurse` can follow a directory link into its target on older PowerShell,
+/// so links are deleted through the non-recursive `Delete` method instead.
+#[cfg(not(unix))]
+const REMOVE_LINK: (&str, &str) = ("(Get-Item -Force -LiteralPath ", ").Delete()");
+
+/// Quotes `path` as a single `sh` word.
+///
+/// Inside single quotes every byte is literal, so only the quote itself needs escaping.
+/// Non-UTF-8 names are kept byte for byte.
+#[cfg(unix)]
+fn quote(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut quoted = vec![b'\''];
+    for &byte in path.as_os_str().as_bytes() {
+        if byte == b'\'' {
+            quoted.extend_from_slice(b"'\\''");
+        } else {
+            quoted.push(byte);
+        }
+    }
+    quoted.push(b'\'');
+    quoted
+}
+
+/// Quotes `path` as a PowerShell literal string, where a quote is escaped by doubling it.
+///
+/// PowerShell also accepts the typographic single quotes as delimiters, so those are doubled too.
+#[cfg(not(unix))]
+fn quote(path: &Path) -> Vec<u8> {
+    let mut quoted = String::from("'");
+    for ch in path.to_string_lossy().chars() {
+        if matches!(ch, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(ch);
+        }
+        quoted.push(ch);
+    }
+    quoted.push('\'');
+    quoted.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    /// Creates an empty directory for one test, removing any left over from an earlier run.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rsdu-script-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn deleting_a_link_to_a_directory_keeps_the_target() {
+        let dir = scratch_dir("link");
+        let target = dir.join("target");
+        fs::create_dir_all(target.join("sub")).unwrap();
+        fs::write(target.join("sub/file"), b"contents").unwrap();
+        let link = dir.join("link");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        let marked = BTreeMap::from([(link.clone(), 0)]);
+        assert!(delete_marked(&marked).is_empty());
+
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert_eq!(fs::read(target.join("sub/file")).unwrap(), b"contents");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn deleting_a_directory_removes_its_contents() {
+        let dir = scratch_dir("dir");
+        let doomed = dir.join("doomed");
+        fs::create_dir_all(doomed.join("sub")).unwrap();
+        fs::write(doomed.join("sub/file"), b"contents").unwrap();
+
+        let marked = BTreeMap::from([(doomed.clone(), 0), (doomed.join("sub/file"), 0)]);
+        assert!(delete_marked(&marked).is_empty());
+
+        assert!(!doomed.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quote_escapes_spaces_and_quotes() {
+        let script = delete_script(&BTreeMap::from([(PathBuf::from("it's a dir/x y"), 0)]));
+        let script = String::from_utf8(script).unwrap();
+        assert!(script
+            .lines()
+            .any(|line| line == r"rm -rf -- 'it'\''s a dir/x y'"));
+        assert_eq!(quote(Path::new("$HOME `id` \\")), b"'$HOME `id` \\'");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn script_deletes_awkward_names_when_run() {
+        let dir = scratch_dir("awkward");
+        let quoted = dir.join("it's a dir");
+        let expanded = dir.join("$(touch pwned) *");
+        fs::create_dir_all(quoted.join("x y")).unwrap();
+        fs::write(quoted.join("x y/file"), b"contents").unwrap();
+        fs::write(quoted.join("kept"), b"contents").unwrap();
+        fs::write(&expanded, b"contents").unwrap();
+
+        let marked = BTreeMap::from([(quoted.join("x y"), 0), (expanded.clone(), 0)]);
+        let script = dir.join("delete.sh");
+        fs::write(&script, delete_script(&marked)).unwrap();
+        let status = process::Command::new("sh")
+            .arg(&script)
+            .current_dir(&dir)
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+        assert!(!quoted.join("x y").exists());
+        assert!(!expanded.exists());
+        assert!(quoted.join("kept").exists());
+        assert!(!dir.join("pwned").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn script_removes_links_without_recursing() {
+        let dir = scratch_dir("script");
+        let link = dir.join("link");
+        std::os::unix::fs::symlink(&dir, &link).unwrap();
+
+        let script =
+            String::from_utf8(delete_script(&BTreeMap::from([(link.clone(), 0)]))).unwrap();
+        assert!(script.contains(&format!("rm -f -- '{}'", link.display())));
+        assert!(!script.contains("rm -rf"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
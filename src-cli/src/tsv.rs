@@ -0,0 +1,63 @@
+/// Escapes characters that would break the line-and-tab layout of a store file.
+pub fn escape_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverses [`escape_field`].
+pub fn unescape_field(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('t') => unescaped.push('\t'),
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_survive_a_round_trip_through_lines() {
+        let fields = [
+            "plain",
+            "tab\there",
+            "new\nline",
+            "ends in cr\r",
+            "cr\r\nlf",
+            "back\\slash\\",
+        ];
+        let text: String = fields
+            .iter()
+            .map(|field| escape_field(field) + "\n")
+            .collect();
+        let read: Vec<_> = text.lines().map(unescape_field).collect();
+        assert_eq!(read, fields);
+    }
+
+    #[test]
+    fn escaped_fields_hold_no_layout_characters() {
+        let escaped = escape_field("a\tb\nc\rd");
+        assert_eq!(escaped, "a\\tb\\nc\\rd");
+    }
+}
@@ -0,0 +1,810 @@
+use crate::app::{
+    apply_key, moved_selection, scroll_offset_for, AppState, Key, TimedEvent, Timers,
+};
+use crate::columns::{fitted_widths, render_row, with_column, Column, ColumnKind, RowContext};
+use crate::error_list::{error_line, ErrorList};
+use crate::format::{age, entry_name, parse_duration, short_size};
+use crate::keymap::{Action, KeyMap, KEY_ESCAPE};
+use crate::line_edit::LineEditor;
+use librsdu::owners::{owned_by, user_name};
+use librsdu::recent::recently_modified;
+use librsdu::{FileInfo, Metric, Scan, SortOrder};
+use ncurses::{
+    addstr, attroff, attron, clear, clrtoeol, curs_set, def_prog_mode, endwin, getch, getmaxyx,
+    getyx, initscr, keypad, mv, mvaddstr, noecho, refresh, reset_prog_mode, setlocale, stdscr,
+    timeout, LcCategory, A_REVERSE, CURSOR_VISIBILITY, ERR, KEY_BACKSPACE, KEY_DC, KEY_END,
+    KEY_ENTER, KEY_HOME, KEY_LEFT, KEY_RESIZE, KEY_RIGHT,
+};
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Rows taken up by the header, status line, footer and instructions.
+const RESERVED_ROWS: usize = 4;
+
+/// Smallest terminal, as `(columns, rows)`, that the browser will draw in.
+const MIN_SCREEN_SIZE: (i32, i32) = (40, 10);
+
+/// Actions listed in the instructions line, with their descriptions.
+const HINTS: &[(Action, &str)] = &[
+    (Action::Navigate(Key::Quit), "quit"),
+    (Action::Navigate(Key::Enter), "open"),
+    (Action::Navigate(Key::Back), "back"),
+    (Action::Navigate(Key::SortBySize), "sort size"),
+    (Action::Navigate(Key::SortByName), "sort name"),
+    (Action::Navigate(Key::ToggleDirsOnly), "dirs only"),
+    (Action::Navigate(Key::ToggleReclaimable), "space freed"),
+    (Action::Navigate(Key::ToggleMark), "mark"),
+    (Action::Annotate, "annotate"),
+    (Action::ShowErrors, "errors"),
+    (Action::Search, "search"),
+];
+
+/// Number of files listed in the recently modified view.
+const RECENT_FILES: usize = 100;
+
+/// Rows taken up by the header, summary and instructions of full-screen lists.
+const LIST_RESERVED_ROWS: usize = 3;
+
+/// Runs the ncurses browser until the user quits. `rescan` reads the tree again for
+/// `--refresh`.
+pub fn run(
+    app_state: &mut AppState,
+    columns: &[Column],
+    keymap: &KeyMap,
+    rescan: &dyn Fn() -> io::Result<Scan>,
+) {
+    // Initialize ncurses, using the user's locale so UTF-8 names render correctly.
+    setlocale(LcCategory::all, "");
+    initscr();
+    keypad(stdscr(), true);
+    noecho();
+    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+    let mut timers = Timers::new(app_state, Instant::now());
+
+    if !wait_for_usable_size(keymap) {
+        endwin();
+        return;
+    }
+    set_key_timeout(timers.idle_wait(Instant::now()));
+    if let Some(path) = app_state.resume_at.take() {
+        draw(app_state, columns, keymap);
+        if confirm(&format!("Resume at {}? (y/n)", path.display())) && !app_state.open(&path) {
+            show_message("The saved location is no longer in the scanned tree.");
+        }
+    }
+
+    loop {
+        if !wait_for_usable_size(keymap) {
+            break;
+        }
+        app_state.clamp_selection(); // Views such as the owner list may have changed the listing
+        draw(app_state, columns, keymap);
+
+        // Handle user input, giving up with `ERR` when a timer is due.
+        set_key_timeout(timers.wait(Instant::now()));
+        let ch = getch();
+        if ch == ERR {
+            match timers.due(Instant::now()) {
+                Some(TimedEvent::Quit) => break,
+                Some(TimedEvent::ResetToRoot) => app_state.reset_to_root(),
+                Some(TimedEvent::Refresh) => refresh_scan(app_state, rescan),
+                None => {}
+            }
+            continue;
+        }
+        timers.key_pressed(Instant::now());
+        // Prompts and lists read keys too; they give up only when an idle timer is due.
+        set_key_timeout(timers.idle_wait(Instant::now()));
+        let keep_running = match keymap.action(ch) {
+            Some(Action::Navigate(key)) => apply_key(app_state, key, viewport_rows()),
+            Some(Action::Annotate) => {
+                edit_annotation(app_state);
+                true
+            }
+            Some(Action::ShowErrors) => {
+                show_errors(app_state, keymap);
+                true
+            }
+            Some(Action::SameOwner) => {
+                show_same_owner(app_state, keymap);
+                true
+            }
+            Some(Action::RecentFiles) => {
+                show_recent_files(app_state, keymap);
+                true
+            }
+            Some(Action::Shell) => {
+                open_shell(app_state);
+                true
+            }
+            Some(Action::TimeFilter) => {
+                edit_time_filter(app_state);
+                true
+            }
+            Some(Action::FindDuplicates) => {
+                show_status("Comparing file contents...");
+                app_state.find_duplicates();
+                true
+            }
+            Some(Action::Search) => {
+                search(app_state);
+                true
+            }
+            None => true,
+        };
+        if !keep_running {
+            break;
+        }
+    }
+
+    // End ncurses mode.
+    endwin();
+}
+
+/// Makes reading a key give up with `ERR` after `wait`, or wait for ever for `None`.
+fn set_key_timeout(wait: Option<Duration>) {
+    // Round up, so a timer is never polled for before it is due.
+    timeout(wait.map_or(-1, |wait| {
+        i32::try_from(wait.as_micros().div_ceil(1000)).unwrap_or(i32::MAX)
+    }));
+}
+
+/// Reads the tree again with `rescan` and shows it in place of the old one, keeping the
+/// old tree if the root can no longer be read.
+fn refresh_scan(app_state: &mut AppState, rescan: &dyn Fn() -> io::Result<Scan>) {
+    show_status("Rescanning...");
+    match rescan() {
+        Ok(scan) => app_state.replace_scan(scan),
+        Err(e) => app_state.incomplete = Some(format!("Rescan failed: {e}")),
+    }
+}
+
+/// Renders the current directory listing, footer and instructions.
+fn draw(app_state: &AppState, columns: &[Column], keymap: &KeyMap) {
+    // Clear the screen and get the current directory info.
+    clear();
+    let current_dir = app_state.current_dir();
+    let entries = app_state.entries();
+
+    // Get the window size.
+    let (max_y, max_x) = screen_size();
+
+    // Display the header line with the current directory path.
+    let width = usize::try_from(max_x).unwrap_or(0);
+    let filter = app_state
+        .modified_within
+        .map(|window| format!(", modified within {}", age(window)))
+        .unwrap_or_default();
+    let search = app_state
+        .name_filter
+        .as_ref()
+        .map(|needle| format!(", names containing '{needle}'"))
+        .unwrap_or_default();
+    let view = format!("[{}{filter}{search}]", app_state.metric.label());
+    let path = if app_state.show_path_sizes {
+        path_with_sizes(
+            &app_state.stack,
+            width.saturating_sub(6 + view.chars().count()),
+        )
+    } else {
+        current_dir.path.display().to_string()
+    };
+    let path = format!("{path} {view}");
+    let header = format!(
+        "--- {path} {}",
+        "-".repeat(width.saturating_sub(path.chars().count() + 5))
+    );
+    mvaddstr(0, 0, &header);
+
+    // Find the maximum size among entries for bar graph scaling.
+    let context = RowContext {
+        max_size: entries
+            .iter()
+            .map(|e| app_state.metric.value(e))
+            .max()
+            .unwrap_or(1),
+        dir_size: app_state.metric.whole(current_dir),
+        annotations: &app_state.annotations,
+        marked: &app_state.marked,
+        size_style: app_state.size_style,
+        metric: app_state.metric,
+        classify: app_state.classify,
+        duplicates: app_state.duplicates.as_ref(),
+    };
+
+    // Display the visible window of files and directories, with the slack column if enabled
+    // and the subdirectory count while sorting by it.
+    let slack_column = ColumnKind::Slack.column();
+    let subdirs_column = ColumnKind::Subdirs.column();
+    let mut columns: Vec<&Column> = columns.iter().collect();
+    if app_state.show_slack {
+        columns = with_column(&columns, &slack_column);
+    }
+    if app_state.sort_order() == Some(SortOrder::Subdirs) {
+        columns = with_column(&columns, &subdirs_column);
+    }
+    let widths = fitted_widths(&columns, &entries, &context);
+    if let Some(message) = app_state.empty_listing_message() {
+        mvaddstr(1, 2, message);
+    }
+    let visible_rows = viewport_rows();
+    for ((i, entry), y_pos) in entries
+        .iter()
+        .enumerate()
+        .skip(app_state.scroll_offset)
+        .take(visible_rows)
+        .zip(1..)
+    {
+        if i == app_state.selected_index {
+            attron(A_REVERSE());
+        }
+
+        mvaddstr(y_pos, 0, &render_row(&columns, &widths, entry, &context));
+
+        if i == app_state.selected_index {
+            attroff(A_REVERSE());
+        }
+    }
+
+    // Display details about the selected entry on the status line.
+    mvaddstr(
+        max_y - 3,
+        0,
+        &status_line(app_state, entries.get(app_state.selected_index).copied()),
+    );
+
+    // Display the footer with total disk usage, apparent size, items and scan errors.
+    let root = &app_state.stack[0];
+    let disk_usage = app_state.size_style.format(root.allocated_size);
+    let apparent_size = app_state.size_style.format(root.size);
+    let mut footer = format!(
+        "*Total disk usage: {disk_usage:>10}   Apparent size: {apparent_size:>10}   Items: {}",
+        root.contained_items()
+    );
+    if let Some(errors) = app_state.error_summary() {
+        footer.push_str("   ");
+        footer.push_str(&errors);
+    }
+    if let Some(note) = &app_state.incomplete {
+        footer.push_str("   ");
+        footer.push_str(note);
+    }
+    mvaddstr(max_y - 2, 0, &footer);
+
+    // Display instructions for the configured keys.
+    let hints: Vec<String> = HINTS
+        .iter()
+        .filter(|&&(action, _)| keymap.is_bound(action))
+        .map(|&(action, description)| format!("{}:{description}", keymap.label(action)))
+        .collect();
+    mvaddstr(max_y - 1, 0, &hints.join("  "));
+
+    refresh();
+}
+
+/// Renders the path of the current directory with each level's size, e.g.
+/// `/home(120G)/me(45G)/projects(12G)`.
+///
+/// If the result is wider than `max_width`, leading levels are replaced with `...`.
+fn path_with_sizes(stack: &[FileInfo], max_width: usize) -> String {
+    let segments: Vec<String> = stack
+        .iter()
+        .enumerate()
+        .map(|(depth, dir)| {
+            let name = if depth == 0 {
+                dir.path.display().to_string()
+            } else {
+                format!("/{}", entry_name(dir))
+            };
+            format!("{name}({})", short_size(dir.size))
+        })
+        .collect();
+
+    let mut start = 0;
+    loop {
+        let path: String = segments[start..].concat();
+        let path = if start == 0 {
+            path
+        } else {
+            format!("...{path}")
+        };
+        if path.chars().count() <= max_width || start + 1 >= segments.len() {
+            return path;
+        }
+        start += 1;
+    }
+}
+
+/// Describes the selected entry: its note, its largest child if it is a directory and, if
+/// enabled, the space deleting it would free.
+///
+/// Also counts the entries marked for the delete script and, if enabled, shows how much
+/// memory the scanned tree takes.
+fn status_line(app_state: &AppState, entry: Option<&FileInfo>) -> String {
+    let mut parts = Vec::new();
+    if let Some(note) = entry.and_then(|entry| app_state.annotations.get(&entry.path)) {
+        parts.push(format!("Note: {note}"));
+    }
+    if let Some(largest) = entry
+        .and_then(|entry| entry.children.as_deref())
+        .and_then(|children| children.iter().max_by_key(|child| child.size))
+    {
+        parts.push(format!(
+            "Largest: {} ({})",
+            entry_name(largest),
+            app_state.size_style.format(largest.size)
+        ));
+    }
+    if let Some(dir) = entry.filter(|entry| entry.is_dir && app_state.metric == Metric::Exclusive) {
+        let in_subdirs: u64 = dir
+            .children
+            .iter()
+            .flatten()
+            .filter(|child| child.is_dir)
+            .map(|child| child.size)
+            .sum();
+        parts.push(format!(
+            "Files directly inside: {}   In subdirectories: {}",
+            app_state.size_style.format(dir.exclusive_size()),
+            app_state.size_style.format(in_subdirs)
+        ));
+    }
+    if let Some(entry) = entry.filter(|entry| entry.xattr_size > 0) {
+        parts.push(format!(
+            "Extended attributes: {}",
+            app_state.size_style.format(entry.xattr_size)
+        ));
+    }
+    if let Some(entry) = entry.filter(|_| app_state.show_reclaimable) {
+        let freed = app_state.size_style.format(entry.allocated_size);
+        parts.push(app_state.fs_usage.map_or_else(
+            || format!("Deleting frees {freed}"),
+            |usage| {
+                format!(
+                    "Deleting frees {freed}: disk {:.1}% -> {:.1}% full",
+                    usage.percent_used(),
+                    usage.after_freeing(entry.allocated_size).percent_used()
+                )
+            },
+        ));
+    }
+    if !app_state.marked.is_empty() {
+        let marked_size = app_state.marked.values().sum();
+        parts.push(format!(
+            "Marked: {} ({})",
+            app_state.marked.len(),
+            app_state.size_style.format(marked_size)
+        ));
+    }
+    if let Some(duplicates) = &app_state.duplicates {
+        let wasted = duplicates.wasted_under(&app_state.current_dir().path);
+        parts.push(format!(
+            "Wasted on duplicates: {}",
+            app_state.size_style.format(wasted)
+        ));
+    }
+    if let Some(memory) = app_state.memory_readout {
+        parts.push(format!(
+            "Tree memory: ~{} for {} entries",
+            app_state.size_style.format(memory),
+            app_state.stack[0].items
+        ));
+    }
+    parts.join("   ")
+}
+
+/// Returns the number of entry rows that fit on the screen.
+fn viewport_rows() -> usize {
+    let (max_y, _) = screen_size();
+    usize::try_from(max_y)
+        .unwrap_or(0)
+        .saturating_sub(RESERVED_ROWS)
+}
+
+/// Shows the scan errors in a scrollable list until the user goes back.
+fn show_errors(app_state: &AppState, keymap: &KeyMap) {
+    if app_state.scan_errors.is_empty() {
+        show_message("No errors during the scan.");
+        return;
+    }
+    let mut list = ErrorList::new(&app_state.scan_errors);
+
+    loop {
+        if !wait_for_usable_size(keymap) {
+            break;
+        }
+        draw_error_list(&list, keymap);
+
+        let ch = getch();
+        if ch == KEY_ESCAPE || ch == ERR {
+            break;
+        }
+        let rows = list_rows();
+        match keymap.action(ch) {
+            Some(Action::Navigate(key)) => {
+                if !list.apply_key(key, rows) {
+                    break;
+                }
+            }
+            Some(Action::Search) => {
+                if let Some(filter) = prompt("Filter: ", list.filter()) {
+                    list.set_filter(filter);
+                }
+            }
+            Some(Action::ShowErrors) => break,
+            Some(
+                Action::Annotate
+                | Action::SameOwner
+                | Action::RecentFiles
+                | Action::Shell
+                | Action::TimeFilter
+                | Action::FindDuplicates,
+            )
+            | None => {}
+        }
+    }
+}
+
+/// Lists everything in the tree owned by the selected entry's owner, and jumps to the
+/// entry the user picks.
+fn show_same_owner(app_state: &mut AppState, keymap: &KeyMap) {
+    let Some(uid) = app_state
+        .entries()
+        .get(app_state.selected_index)
+        .and_then(|entry| entry.owner)
+    else {
+        show_message("Ownership is not known for this entry.");
+        return;
+    };
+    let owner_label = user_name(uid).map_or_else(
+        || format!("uid {uid}"),
+        |name| format!("{name} (uid {uid})"),
+    );
+
+    let owned = owned_by(&app_state.stack[0], uid);
+    let total: u64 = owned.iter().map(|entry| entry.size).sum();
+    let lines: Vec<String> = owned
+        .iter()
+        .map(|entry| {
+            format!(
+                "{:>10}  {}",
+                app_state.size_style.format(entry.size),
+                entry.path.display()
+            )
+        })
+        .collect();
+    let summary = format!(
+        "{} entries, {} in total",
+        owned.len(),
+        app_state.size_style.format(total)
+    );
+
+    let picked = pick(&format!("Owned by {owner_label}"), &summary, &lines, keymap)
+        .map(|index| owned[index].path.clone());
+    if let Some(path) = picked {
+        app_state.reveal(&path);
+        app_state.scroll_to_selection(viewport_rows());
+    }
+}
+
+/// Lists the most recently modified files in the whole tree, with their size and age.
+/// Choosing one reveals it in the listing.
+fn show_recent_files(app_state: &mut AppState, keymap: &KeyMap) {
+    let now = SystemTime::now();
+    let recent = recently_modified(&app_state.stack[0], RECENT_FILES);
+    let total: u64 = recent.iter().map(|entry| entry.size).sum();
+    let lines: Vec<String> = recent
+        .iter()
+        .map(|entry| {
+            // Times in the future (clock skew) read as modified just now.
+            let elapsed = entry
+                .modified
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            format!(
+                "{:>10} {:>5} ago  {}",
+                app_state.size_style.format(entry.size),
+                age(elapsed),
+                entry.path.display()
+            )
+        })
+        .collect();
+    let summary = format!(
+        "{} most recently modified files, {} in total",
+        recent.len(),
+        app_state.size_style.format(total)
+    );
+
+    let picked = pick("Recently modified files", &summary, &lines, keymap)
+        .map(|index| recent[index].path.clone());
+    if let Some(path) = picked {
+        app_state.reveal(&path);
+        app_state.scroll_to_selection(viewport_rows());
+    }
+}
+
+/// Suspends the TUI and runs the user's shell in the selected directory, or in the
+/// directory being viewed if a file is selected. The TUI resumes when the shell exits.
+fn open_shell(app_state: &AppState) {
+    let dir = app_state
+        .entries()
+        .get(app_state.selected_index)
+        .filter(|entry| entry.is_dir)
+        .map_or_else(|| &app_state.current_dir().path, |entry| &entry.path);
+    let shell = user_shell();
+
+    def_prog_mode();
+    endwin();
+    println!(
+        "Starting {} in {}. Exit the shell to return to rsdu.",
+        shell.to_string_lossy(),
+        dir.display()
+    );
+    let status = Command::new(&shell).current_dir(dir).status();
+    reset_prog_mode();
+    refresh();
+
+    if let Err(e) = status {
+        show_message(&format!("Could not start {}: {e}", shell.to_string_lossy()));
+    }
+}
+
+/// Returns the shell to run: `$SHELL`, or the platform's default shell.
+fn user_shell() -> OsString {
+    let (variable, fallback) = if cfg!(windows) {
+        ("COMSPEC", "cmd.exe")
+    } else {
+        ("SHELL", "/bin/sh")
+    };
+    env::var_os(variable)
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| fallback.into())
+}
+
+/// Shows `lines` in a scrollable list under `title` and `summary`.
+///
+/// Returns the index of the line chosen with Enter, or `None` if the user goes back.
+fn pick(title: &str, summary: &str, lines: &[String], keymap: &KeyMap) -> Option<usize> {
+    let mut selected = 0;
+    let mut offset = 0;
+    loop {
+        if !wait_for_usable_size(keymap) {
+            return None;
+        }
+        clear();
+        let (max_y, _) = screen_size();
+        let rows = list_rows();
+        mvaddstr(0, 0, &format!("--- {title} ---"));
+        mvaddstr(1, 0, summary);
+        for ((i, line), y_pos) in lines.iter().enumerate().skip(offset).take(rows).zip(2..) {
+            if i == selected {
+                attron(A_REVERSE());
+            }
+            mvaddstr(y_pos, 0, line);
+            if i == selected {
+                attroff(A_REVERSE());
+            }
+        }
+        mvaddstr(
+            max_y - 1,
+            0,
+            &format!(
+                "{}:go to  {}:back",
+                keymap.label(Action::Navigate(Key::Enter)),
+                keymap.label(Action::Navigate(Key::Back))
+            ),
+        );
+        refresh();
+
+        let ch = getch();
+        if ch == KEY_ESCAPE || ch == ERR {
+            return None;
+        }
+        match keymap.action(ch) {
+            Some(Action::Navigate(Key::Enter)) if !lines.is_empty() => return Some(selected),
+            Some(Action::Navigate(Key::Back | Key::Quit)) => return None,
+            Some(Action::Navigate(key)) => {
+                selected = moved_selection(key, selected, lines.len(), rows.max(1));
+                offset = scroll_offset_for(selected, offset, rows);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Renders the error list: a summary by kind, the visible errors and the instructions.
+fn draw_error_list(list: &ErrorList, keymap: &KeyMap) {
+    clear();
+    let (max_y, _) = screen_size();
+    let errors = list.visible();
+
+    mvaddstr(0, 0, "--- Scan errors ---");
+    mvaddstr(1, 0, &list.summary());
+
+    for ((i, error), y_pos) in errors
+        .iter()
+        .enumerate()
+        .skip(list.scroll_offset)
+        .take(list_rows())
+        .zip(2..)
+    {
+        if i == list.selected_index {
+            attron(A_REVERSE());
+        }
+        mvaddstr(y_pos, 0, &error_line(error));
+        if i == list.selected_index {
+            attroff(A_REVERSE());
+        }
+    }
+
+    let filter = if list.filter().is_empty() {
+        String::new()
+    } else {
+        format!("Filter '{}': {} shown   ", list.filter(), errors.len())
+    };
+    mvaddstr(
+        max_y - 1,
+        0,
+        &format!(
+            "{filter}{}:back  {}:filter",
+            keymap.label(Action::Navigate(Key::Back)),
+            keymap.label(Action::Search)
+        ),
+    );
+    refresh();
+}
+
+/// Returns the number of rows a full-screen list can show.
+fn list_rows() -> usize {
+    let (max_y, _) = screen_size();
+    usize::try_from(max_y)
+        .unwrap_or(0)
+        .saturating_sub(LIST_RESERVED_ROWS)
+}
+
+/// Prompts for a note on the selected entry and saves it. An empty note removes it.
+fn edit_annotation(app_state: &mut AppState) {
+    let Some(path) = app_state
+        .entries()
+        .get(app_state.selected_index)
+        .map(|entry| entry.path.clone())
+    else {
+        return;
+    };
+    let current = app_state.annotations.get(&path).unwrap_or("").to_string();
+
+    if let Some(note) = prompt("Note (empty removes): ", &current) {
+        if let Err(e) = app_state.annotations.set(&path, &note) {
+            show_message(&format!("Could not save annotation: {e}"));
+        }
+    }
+}
+
+/// Asks a yes-or-no `question` on the status row, returning whether it was answered yes.
+fn confirm(question: &str) -> bool {
+    let (max_y, _) = screen_size();
+    mvaddstr(max_y - 3, 0, question);
+    clrtoeol();
+    refresh();
+    matches!(u8::try_from(getch()), Ok(b'y' | b'Y'))
+}
+
+/// Asks for text and shows only the entries of the current directory whose names contain
+/// it. An empty answer or Escape shows them all again.
+fn search(app_state: &mut AppState) {
+    let current = app_state.name_filter.clone().unwrap_or_default();
+    app_state.set_name_filter(prompt("Search (empty shows all): ", &current));
+}
+
+/// Asks for a time window such as `7d` and shows only what was modified within it.
+/// An empty answer shows the whole tree again.
+fn edit_time_filter(app_state: &mut AppState) {
+    let current = app_state.modified_within.map(age).unwrap_or_default();
+    let Some(input) = prompt("Modified within (e.g. 7d, empty shows all): ", &current) else {
+        return;
+    };
+    if input.trim().is_empty() {
+        app_state.filter_modified_within(None);
+        return;
+    }
+    match parse_duration(&input) {
+        Ok(window) => app_state.filter_modified_within(Some(window)),
+        Err(e) => show_message(&e),
+    }
+}
+
+/// Reads a line of text on the status row, starting from `initial`.
+///
+/// Enter accepts the input and Escape cancels it, returning `None`.
+fn prompt(label: &str, initial: &str) -> Option<String> {
+    let (max_y, _) = screen_size();
+    let mut editor = LineEditor::new(initial);
+
+    curs_set(CURSOR_VISIBILITY::CURSOR_VISIBLE);
+    let result = loop {
+        // Draw up to the cursor first, so ncurses works out where it goes even for wide
+        // characters, then the rest of the line.
+        let (before, after) = editor.split_at_cursor();
+        mvaddstr(max_y - 3, 0, &format!("{label}{before}"));
+        let (mut cursor_y, mut cursor_x) = (0, 0);
+        getyx(stdscr(), &mut cursor_y, &mut cursor_x);
+        addstr(&after);
+        clrtoeol();
+        mv(cursor_y, cursor_x);
+        refresh();
+
+        match getch() {
+            KEY_ENTER | 10 => break Some(editor.text()),
+            KEY_ESCAPE | ERR => break None,
+            KEY_BACKSPACE | 127 | 8 => editor.backspace(),
+            KEY_DC => editor.delete(),
+            KEY_LEFT => editor.left(),
+            KEY_RIGHT => editor.right(),
+            KEY_HOME => editor.home(),
+            KEY_END => editor.end(),
+            ch => {
+                if let Ok(byte) = u8::try_from(ch) {
+                    editor.push_byte(byte);
+                } // Other function keys are ignored
+            }
+        }
+    };
+    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+    result
+}
+
+/// Shows `message` on the status row until a key is pressed.
+fn show_message(message: &str) {
+    show_status(message);
+    getch();
+}
+
+/// Shows `message` on the status row, without waiting, while something slow runs.
+fn show_status(message: &str) {
+    let (max_y, _) = screen_size();
+    mvaddstr(max_y - 3, 0, message);
+    clrtoeol();
+    refresh();
+}
+
+/// Shows a message instead of the view while the terminal is smaller than
+/// [`MIN_SCREEN_SIZE`], rechecking whenever it is resized.
+///
+/// Returns `false` if the user quits while waiting.
+fn wait_for_usable_size(keymap: &KeyMap) -> bool {
+    let (min_x, min_y) = MIN_SCREEN_SIZE;
+    loop {
+        let (max_y, max_x) = screen_size();
+        if max_x >= min_x && max_y >= min_y {
+            return true;
+        }
+        clear();
+        mvaddstr(0, 0, &format!("Terminal too small ({max_x}x{max_y})"));
+        mvaddstr(1, 0, &format!("Need at least {min_x}x{min_y}"));
+        if keymap.is_bound(Action::Navigate(Key::Quit)) {
+            mvaddstr(
+                2,
+                0,
+                &format!("{}:quit", keymap.label(Action::Navigate(Key::Quit))),
+            );
+        }
+        refresh();
+
+        set_key_timeout(None); // Timers wait until there is room to show their effect
+        let ch = getch();
+        if ch != KEY_RESIZE && keymap.action(ch) == Some(Action::Navigate(Key::Quit)) {
+            return false;
+        }
+    }
+}
+
+/// Returns the terminal size as `(rows, columns)`.
+fn screen_size() -> (i32, i32) {
+    let mut y = 0;
+    let mut x = 0;
+    getmaxyx(stdscr(), &mut y, &mut x);
+    (y, x)
+}
@@ -0,0 +1,164 @@
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// What the scanner needs to know about one entry, read without following symbolic links.
+#[derive(Debug, Clone, Copy)]
+pub struct EntryStat {
+    pub is_dir: bool,
+    pub len: u64,                     // Apparent size in bytes
+    pub allocated: u64,               // Bytes occupied on disk
+    pub id: (u64, u64),               // (device, inode), for spotting repeated hard links
+    pub links: u64,                   // Number of hard links to the entry
+    pub owner: Option<u32>,           // User ID of the owner, on platforms that have one
+    pub modified: Option<SystemTime>, // Last modification time, where the platform reports it
+}
+
+impl EntryStat {
+    /// Reads the entry at `path` itself.
+    pub fn of_path(path: &Path) -> io::Result<Self> {
+        fs::symlink_metadata(path).map(|metadata| Self::from_metadata(&metadata))
+    }
+
+    /// Converts metadata returned by the standard library.
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        Self {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            allocated: metadata.blocks() * 512, // st_blocks is always in 512-byte units
+            id: (metadata.dev(), metadata.ino()),
+            links: metadata.nlink(),
+            owner: Some(metadata.uid()),
+            modified: metadata.modified().ok(),
+        }
+    }
+
+    /// Converts metadata returned by the standard library.
+    ///
+    /// Allocation, hard link counts and owner IDs are not exposed on this platform, so the
+    /// allocated size is the apparent size and every entry counts as a single link.
+    #[cfg(not(unix))]
+    pub fn from_metadata(metadata: &fs::Metadata) -> Self {
+        Self {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            allocated: metadata.len(),
+            id: (0, 0),
+            links: 1,
+            owner: None,
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// An open directory whose entries can be read and opened relative to it.
+///
+/// Entries are opened with `openat` and read with `fstatat` relative to this handle, so the
+/// kernel never sees the full path: trees deeper than `PATH_MAX` scan normally, and a
+/// directory renamed mid-scan cannot redirect the walk elsewhere.
+#[cfg(unix)]
+pub struct DirHandle(std::os::fd::OwnedFd);
+
+/// A directory whose entries are read through its path, on platforms without `openat`.
+#[cfg(not(unix))]
+pub struct DirHandle(std::path::PathBuf);
+
+#[cfg(unix)]
+impl DirHandle {
+    /// Opens the directory at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::open_at(nix::fcntl::AT_FDCWD, path.as_os_str())
+    }
+
+    /// Opens the directory `name` inside this one. `_path` is its full path, which is only
+    /// needed on other platforms.
+    pub fn open_child(&self, name: &OsStr, _path: &Path) -> io::Result<Self> {
+        Self::open_at(&self.0, name)
+    }
+
+    /// Reads the entry `name` inside this directory.
+    pub fn stat_child(&self, name: &OsStr, _path: &Path) -> io::Result<EntryStat> {
+        use nix::fcntl::AtFlags;
+        let stat = nix::sys::stat::fstatat(&self.0, name, AtFlags::AT_SYMLINK_NOFOLLOW)?;
+        Ok(stat_to_entry(&stat))
+    }
+
+    /// Returns the names of the entries in this directory, leaving out `.` and `..`.
+    pub fn names(&self) -> io::Result<Vec<OsString>> {
+        use std::os::unix::ffi::OsStrExt;
+        // The listing gets its own descriptor, so reading it does not tie up this handle.
+        let mut dir = nix::dir::Dir::from_fd(self.0.try_clone()?)?;
+        let mut names = Vec::new();
+        for entry in dir.iter() {
+            let name = entry?.file_name().to_bytes().to_vec();
+            if name != b"." && name != b".." {
+                names.push(OsStr::from_bytes(&name).to_os_string());
+            }
+        }
+        Ok(names)
+    }
+
+    /// Opens the directory `path`, relative to `dir` unless it is absolute.
+    fn open_at(dir: impl std::os::fd::AsFd, path: &OsStr) -> io::Result<Self> {
+        use nix::fcntl::{openat, OFlag};
+        use nix::sys::stat::Mode;
+        let flags = OFlag::O_RDONLY | OFlag::O_DIRECTORY | OFlag::O_NOFOLLOW | OFlag::O_CLOEXEC;
+        Ok(Self(openat(dir, path, flags, Mode::empty())?))
+    }
+}
+
+#[cfg(not(unix))]
+impl DirHandle {
+    /// Opens the directory at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self(path.to_path_buf()))
+    }
+
+    /// Opens the directory `_name` inside this one, whose full path is `path`.
+    pub fn open_child(&self, _name: &OsStr, path: &Path) -> io::Result<Self> {
+        Ok(Self(path.to_path_buf()))
+    }
+
+    /// Reads the entry `_name` inside this directory, whose full path is `path`.
+    pub fn stat_child(&self, _name: &OsStr, path: &Path) -> io::Result<EntryStat> {
+        EntryStat::of_path(path)
+    }
+
+    /// Returns the names of the entries in this directory.
+    pub fn names(&self) -> io::Result<Vec<OsString>> {
+        fs::read_dir(&self.0)?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect()
+    }
+}
+
+/// Converts the result of `fstatat`.
+#[cfg(unix)]
+#[allow(clippy::useless_conversion, clippy::unnecessary_fallible_conversions)] // Field types vary by platform
+fn stat_to_entry(stat: &nix::sys::stat::FileStat) -> EntryStat {
+    use nix::sys::stat::SFlag;
+    use std::time::Duration;
+
+    let file_type = SFlag::from_bits_truncate(stat.st_mode) & SFlag::S_IFMT;
+    let secs = Duration::from_secs(stat.st_mtime.unsigned_abs());
+    let nanos = Duration::from_nanos(u64::try_from(stat.st_mtime_nsec).unwrap_or(0));
+    let modified = if stat.st_mtime >= 0 {
+        SystemTime::UNIX_EPOCH.checked_add(secs)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(secs)
+    }
+    .and_then(|time| time.checked_add(nanos));
+    EntryStat {
+        is_dir: file_type == SFlag::S_IFDIR,
+        len: u64::try_from(stat.st_size).unwrap_or(0),
+        allocated: u64::try_from(stat.st_blocks).unwrap_or(0) * 512, // Always 512-byte units
+        id: (u64::from(stat.st_dev), u64::from(stat.st_ino)),
+        links: u64::from(stat.st_nlink),
+        owner: Some(stat.st_uid),
+        modified,
+    }
+}
@@ -0,0 +1,159 @@
+use crate::FileInfo;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Size of the chunks files are read in while comparing them.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A set of two or more files with identical contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub size: u64,           // Apparent size of each copy
+    pub paths: Vec<PathBuf>, // Every copy, in tree order
+}
+
+/// The files in a tree whose contents are the same as another file's.
+#[derive(Debug, Default)]
+pub struct Duplicates {
+    groups: Vec<DuplicateGroup>,
+    group_of: HashMap<PathBuf, usize>, // Index into `groups` for each copy
+}
+
+impl Duplicates {
+    /// Returns every group of identical files.
+    #[must_use]
+    pub fn groups(&self) -> &[DuplicateGroup] {
+        &self.groups
+    }
+
+    /// Returns `true` if `path` has the same contents as some other file in the tree.
+    #[must_use]
+    pub fn is_duplicate(&self, path: &Path) -> bool {
+        self.group_of.contains_key(path)
+    }
+
+    /// Returns the bytes under `dir` that could be freed while keeping one copy of every
+    /// file's contents somewhere in the tree.
+    ///
+    /// A group with all its copies under `dir` wastes all but one of them; if a copy is kept
+    /// elsewhere, every copy under `dir` is redundant.
+    #[must_use]
+    pub fn wasted_under(&self, dir: &Path) -> u64 {
+        self.groups
+            .iter()
+            .map(|group| {
+                let inside = group
+                    .paths
+                    .iter()
+                    .filter(|path| path.starts_with(dir))
+                    .count() as u64;
+                let redundant = if inside == group.paths.len() as u64 {
+                    inside.saturating_sub(1)
+                } else {
+                    inside
+                };
+                redundant * group.size
+            })
+            .sum()
+    }
+}
+
+/// Finds the files below `root` whose contents are identical.
+///
+/// Only files of the same size are read, and each is compared byte for byte with the
+/// first copy of its group, so a hash collision never reports different files as copies.
+/// Empty files, symbolic links, hard links already counted by the scan (which have a size
+/// of 0) and files that cannot be read are never reported.
+#[must_use]
+pub fn find_duplicates(root: &FileInfo) -> Duplicates {
+    let mut by_size: HashMap<u64, Vec<&Path>> = HashMap::new();
+    for file in root.files() {
+        if file.size > 0 && is_regular_file(&file.path) {
+            by_size.entry(file.size).or_default().push(&file.path);
+        }
+    }
+
+    let mut sizes: Vec<u64> = by_size
+        .iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(&size, _)| size)
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut duplicates = Duplicates::default();
+    for size in sizes {
+        let mut by_hash: HashMap<u64, Vec<Vec<PathBuf>>> = HashMap::new();
+        for &path in &by_size[&size] {
+            let Ok(hash) = content_hash(path) else {
+                continue;
+            };
+            let candidates = by_hash.entry(hash).or_default();
+            let matching = candidates
+                .iter_mut()
+                .find(|copies| same_contents(&copies[0], path).unwrap_or(false));
+            match matching {
+                Some(copies) => copies.push(path.to_path_buf()),
+                None => candidates.push(vec![path.to_path_buf()]),
+            }
+        }
+        for paths in by_hash.into_values().flatten() {
+            if paths.len() > 1 {
+                let index = duplicates.groups.len();
+                for path in &paths {
+                    duplicates.group_of.insert(path.clone(), index);
+                }
+                duplicates.groups.push(DuplicateGroup { size, paths });
+            }
+        }
+    }
+    duplicates
+}
+
+/// Returns `true` if `path` is a regular file rather than a symbolic link or device.
+fn is_regular_file(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_file())
+}
+
+/// Hashes the contents of the file at `path`.
+fn content_hash(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buffer[..read]);
+    }
+}
+
+/// Returns `true` if the files at `a` and `b` hold the same bytes.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let (mut a, mut b) = (File::open(a)?, File::open(b)?);
+    let (mut a_buffer, mut b_buffer) = (vec![0; CHUNK_SIZE], vec![0; CHUNK_SIZE]);
+    loop {
+        let read = read_full(&mut a, &mut a_buffer)?;
+        if read != read_full(&mut b, &mut b_buffer)? || a_buffer[..read] != b_buffer[..read] {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Fills as much of `buffer` as `file` has left, returning how many bytes were read.
+fn read_full(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
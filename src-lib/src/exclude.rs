@@ -0,0 +1,207 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::{RegexSet, RegexSetBuilder};
+use std::fmt;
+use std::path::Path;
+
+/// A set of glob and regex patterns matched against the entries of a scan.
+///
+/// A glob without a `/` is matched against the entry's file name, so `*.log` excludes log
+/// files at any depth. A glob with a `/` is matched against the full path. Regexes are
+/// always matched against the full path and may match anywhere in it; anchor them with `^`
+/// and `$` to match the whole path.
+#[derive(Debug, Clone, Default)]
+pub struct ExcludeSet {
+    name_globs: GlobSet,
+    path_globs: GlobSet,
+    regexes: Option<RegexSet>,
+}
+
+/// A pattern that could not be compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExcludeError {
+    pub pattern: String,
+    pub message: String,
+}
+
+impl fmt::Display for ExcludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid exclude pattern '{}': {}",
+            self.pattern, self.message
+        )
+    }
+}
+
+impl std::error::Error for ExcludeError {}
+
+impl ExcludeSet {
+    /// Compiles `globs` and `regexes` into a set, matching case-insensitively if
+    /// `ignore_case` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first pattern that is not a valid glob or regex.
+    pub fn new(
+        globs: &[String],
+        regexes: &[String],
+        ignore_case: bool,
+    ) -> Result<Self, ExcludeError> {
+        let mut name_globs = GlobSetBuilder::new();
+        let mut path_globs = GlobSetBuilder::new();
+        for pattern in globs {
+            let glob = GlobBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| ExcludeError {
+                    pattern: pattern.clone(),
+                    message: e.kind().to_string(),
+                })?;
+            if pattern.contains('/') {
+                path_globs.add(glob);
+            } else {
+                name_globs.add(glob);
+            }
+        }
+
+        let regexes = if regexes.is_empty() {
+            None
+        } else {
+            // Compile each regex on its own first, so an error names the bad pattern.
+            for pattern in regexes {
+                RegexSetBuilder::new([pattern])
+                    .case_insensitive(ignore_case)
+                    .build()
+                    .map_err(|e| ExcludeError {
+                        pattern: pattern.clone(),
+                        message: regex_message(&e),
+                    })?;
+            }
+            let set = RegexSetBuilder::new(regexes)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|e| ExcludeError {
+                    pattern: regexes.join(" "),
+                    message: regex_message(&e),
+                })?;
+            Some(set)
+        };
+
+        let build_error = |e: globset::Error| ExcludeError {
+            pattern: e.glob().unwrap_or_default().to_string(),
+            message: e.kind().to_string(),
+        };
+        Ok(Self {
+            name_globs: name_globs.build().map_err(build_error)?,
+            path_globs: path_globs.build().map_err(build_error)?,
+            regexes,
+        })
+    }
+
+    /// Returns `true` if the set has no patterns.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.name_globs.is_empty() && self.path_globs.is_empty() && self.regexes.is_none()
+    }
+
+    /// Returns `true` if `path` matches any pattern in the set.
+    #[must_use]
+    pub fn is_match(&self, path: &Path) -> bool {
+        path.file_name()
+            .is_some_and(|name| self.name_globs.is_match(name))
+            || self.path_globs.is_match(path)
+            || self
+                .regexes
+                .as_ref()
+                .is_some_and(|regexes| regexes.is_match(&path.to_string_lossy()))
+    }
+}
+
+/// Returns the useful part of a regex error, which is otherwise a multi-line report.
+fn regex_message(error: &regex::Error) -> String {
+    match error {
+        regex::Error::Syntax(report) => report
+            .lines()
+            .last()
+            .unwrap_or(report)
+            .trim_start_matches("error: ")
+            .to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(ToString::to_string).collect()
+    }
+
+    fn set(globs: &[&str], regexes: &[&str], ignore_case: bool) -> ExcludeSet {
+        ExcludeSet::new(&strings(globs), &strings(regexes), ignore_case).unwrap()
+    }
+
+    #[test]
+    fn globs_without_a_slash_match_the_file_name_at_any_depth() {
+        let excludes = set(&["*.log"], &[], false);
+        assert!(excludes.is_match(Path::new("/var/app.log")));
+        assert!(excludes.is_match(Path::new("/var/log/deep/app.log")));
+        assert!(!excludes.is_match(Path::new("/var/app.log/inner")));
+    }
+
+    #[test]
+    fn globs_with_a_slash_match_the_full_path() {
+        let excludes = set(&["/var/*/cache"], &[], false);
+        assert!(excludes.is_match(Path::new("/var/lib/cache")));
+        assert!(!excludes.is_match(Path::new("/var/lib/deep/cache")));
+        assert!(!excludes.is_match(Path::new("/home/cache")));
+    }
+
+    #[test]
+    fn regexes_match_anywhere_unless_anchored() {
+        let unanchored = set(&[], &["cache"], false);
+        assert!(unanchored.is_match(Path::new("/home/user/.cache/x")));
+
+        let anchored = set(&[], &["^/home/[^/]+/\\.cache$"], false);
+        assert!(anchored.is_match(Path::new("/home/user/.cache")));
+        assert!(!anchored.is_match(Path::new("/home/user/.cache/x")));
+        assert!(!anchored.is_match(Path::new("/mnt/home/user/.cache")));
+    }
+
+    #[test]
+    fn ignore_case_applies_to_globs_and_regexes() {
+        for ignore_case in [false, true] {
+            let globs = set(&["*.LOG", "/Var/*"], &[], ignore_case);
+            assert_eq!(globs.is_match(Path::new("/tmp/app.log")), ignore_case);
+            assert_eq!(globs.is_match(Path::new("/var/x")), ignore_case);
+
+            let regexes = set(&[], &["/CACHE$"], ignore_case);
+            assert_eq!(regexes.is_match(Path::new("/home/cache")), ignore_case);
+        }
+    }
+
+    #[test]
+    fn errors_name_the_bad_pattern() {
+        let error = ExcludeSet::new(&strings(&["*.log", "[unclosed"]), &[], false).unwrap_err();
+        assert_eq!(error.pattern, "[unclosed");
+        assert!(error
+            .to_string()
+            .starts_with("invalid exclude pattern '[unclosed': "));
+
+        let error = ExcludeSet::new(&[], &strings(&["ok", "(open"]), false).unwrap_err();
+        assert_eq!(error.pattern, "(open");
+        assert!(error
+            .to_string()
+            .starts_with("invalid exclude pattern '(open': "));
+        assert!(!error.message.contains('\n'));
+    }
+
+    #[test]
+    fn an_empty_set_matches_nothing() {
+        let excludes = set(&[], &[], false);
+        assert!(excludes.is_empty());
+        assert!(!excludes.is_match(Path::new("/anything")));
+    }
+}
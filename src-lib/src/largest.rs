@@ -0,0 +1,16 @@
+use crate::FileInfo;
+
+/// Returns the largest file below `root`, or `None` if there are no files.
+///
+/// Directories are left out, so this finds an individual file worth deleting rather than
+/// the folder that holds it. Of files with equal sizes, the first in the tree is returned.
+#[must_use]
+pub fn largest_file(root: &FileInfo) -> Option<&FileInfo> {
+    root.files().reduce(|largest, file| {
+        if file.size > largest.size {
+            file
+        } else {
+            largest
+        }
+    })
+}
@@ -1,63 +1,670 @@
-use std::fs;
-use std::io;
-use std::path::{Path, PathBuf};
+use std::cmp::Ordering;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+mod dir_handle;
+pub mod duplicates;
+pub mod exclude;
+pub mod json;
+pub mod largest;
+pub mod mounts;
+pub mod owners;
+pub mod recent;
+mod scan;
+pub mod summary;
+mod xattrs;
+
+pub use scan::{
+    scan, scan_direct_totals, scan_totals, scan_totals_with, scan_with_progress,
+    traverse_directory, traverse_directory_with, Progress, Scan, ScanError, ScanErrorKind,
+    ScanOptions, Totals, Xattrs,
+};
 
 #[derive(Debug, Clone)]
 pub struct FileInfo {
     pub path: PathBuf,
     pub size: u64,
     pub is_dir: bool,
-    pub children: Option<Vec<FileInfo>>,
-    pub items: u64, // Added this field
+    pub children: Option<Vec<Self>>,
+    pub items: u64,                   // Added this field
+    pub allocated_size: u64,          // Bytes actually used on disk
+    pub owner: Option<u32>,           // User ID of the owner, on platforms that have one
+    pub modified: Option<SystemTime>, // Last modification time, where the platform reports it
+    pub xattr_size: u64, // Bytes of extended attribute names and values; 0 unless the scan read them
 }
 
 impl FileInfo {
     /// Creates a new `FileInfo` instance.
-    pub fn new(path: PathBuf, size: u64, is_dir: bool, items: u64) -> Self {
-        FileInfo {
+    ///
+    /// The allocated size starts out equal to `size`; set `allocated_size` when the
+    /// on-disk usage is known.
+    #[must_use]
+    pub const fn new(path: PathBuf, size: u64, is_dir: bool, items: u64) -> Self {
+        Self {
             path,
             size,
             is_dir,
             children: None,
             items,
+            allocated_size: size,
+            owner: None,
+            modified: None,
+            xattr_size: 0,
         }
     }
-}
 
-/// Recursively traverses a directory and calculates the size of each file and directory.
-pub fn traverse_directory(path: &Path) -> io::Result<FileInfo> {
-    let metadata = fs::metadata(path)?;
-    let is_dir = metadata.is_dir();
-    let mut size = 0;
-    let mut items = 1; // Count the current item
-    let mut children = Vec::new();
+    /// Creates an empty directory entry whose own size, before any children, is `size`.
+    ///
+    /// Use [`add_child`](Self::add_child) or [`with_child`](Self::with_child) to fill it, so
+    /// a tree can be built without touching the filesystem.
+    #[must_use]
+    pub const fn new_dir(path: PathBuf, size: u64) -> Self {
+        Self {
+            path,
+            size,
+            is_dir: true,
+            children: Some(Vec::new()),
+            items: 1,
+            allocated_size: size,
+            owner: None,
+            modified: None,
+            xattr_size: 0,
+        }
+    }
 
-    if is_dir {
-        let read_dir = fs::read_dir(path)?;
-        for entry_result in read_dir {
-            let entry = entry_result?;
-            let child_path = entry.path();
+    /// Appends `child` and adds its sizes and item count to this entry's totals.
+    ///
+    /// This keeps the invariant the scanner maintains: a directory's `size`,
+    /// `allocated_size`, `items` and `xattr_size` are its own contribution plus the sum over
+    /// its children. Only the totals of this entry are updated, so build trees bottom-up, adding
+    /// a subdirectory once it is complete. An entry created as a file becomes a directory.
+    pub fn add_child(&mut self, child: Self) {
+        self.size += child.size;
+        self.allocated_size += child.allocated_size;
+        self.items += child.items;
+        self.xattr_size += child.xattr_size;
+        self.is_dir = true;
+        self.children.get_or_insert_with(Vec::new).push(child);
+    }
 
-            match traverse_directory(&child_path) {
-                Ok(child_info) => {
-                    size += child_info.size;
-                    items += child_info.items;
-                    children.push(child_info);
-                }
-                Err(e) => {
-                    eprintln!("Warning: Could not traverse {}: {}", child_path.display(), e);
-                    continue;
+    /// Like [`add_child`](Self::add_child), but takes and returns `self` for chaining.
+    #[must_use]
+    pub fn with_child(mut self, child: Self) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    /// Returns the bytes lost to block rounding: allocated size beyond the apparent size.
+    ///
+    /// Sparse and compressed files can take less space than their apparent size; their
+    /// slack is 0.
+    #[must_use]
+    pub const fn slack(&self) -> u64 {
+        self.allocated_size.saturating_sub(self.size)
+    }
+
+    /// Returns the number of entries inside this directory at any depth, not counting the
+    /// directory itself. Files have no contents and return 0.
+    ///
+    /// `items` includes the entry itself so that totals add up; this is the count to show
+    /// users, so an empty directory reads as 0 items rather than 1.
+    #[must_use]
+    pub const fn contained_items(&self) -> u64 {
+        if self.is_dir {
+            self.items.saturating_sub(1)
+        } else {
+            0
+        }
+    }
+
+    /// Returns every file at or below this entry, in tree order.
+    ///
+    /// Directories are left out, including ones whose contents were not scanned. A file
+    /// yields just itself.
+    pub fn files(&self) -> impl Iterator<Item = &Self> {
+        let mut pending = vec![self];
+        std::iter::from_fn(move || {
+            while let Some(node) = pending.pop() {
+                match node.children.as_deref() {
+                    Some(children) => pending.extend(children.iter().rev()),
+                    None if !node.is_dir => return Some(node),
+                    None => {}
                 }
             }
+            None
+        })
+    }
+
+    /// Returns the apparent size of the files directly inside this directory, leaving out
+    /// everything in its subdirectories. A file's exclusive size is its own size.
+    #[must_use]
+    pub fn exclusive_size(&self) -> u64 {
+        self.children.as_deref().map_or_else(
+            || if self.is_dir { 0 } else { self.size },
+            |children| {
+                children
+                    .iter()
+                    .filter(|child| !child.is_dir)
+                    .map(|child| child.size)
+                    .sum()
+            },
+        )
+    }
+
+    /// Returns the number of directories directly inside this one. Files return 0.
+    #[must_use]
+    pub fn subdirectory_count(&self) -> u64 {
+        self.children
+            .as_deref()
+            .unwrap_or(&[])
+            .iter()
+            .filter(|child| child.is_dir)
+            .count() as u64
+    }
+
+    /// Estimates the memory held by the tree below this entry, in bytes.
+    ///
+    /// Counts every node, the buffer behind each path, and the spare capacity of each
+    /// children vector. Allocator overhead is not included, so the real figure is a little
+    /// higher.
+    #[must_use]
+    pub fn memory_usage(&self) -> u64 {
+        size_of::<Self>() as u64 + self.heap_usage()
+    }
+
+    /// Returns the bytes this node and its descendants own on the heap.
+    fn heap_usage(&self) -> u64 {
+        let path = self.path.capacity() as u64;
+        let children = self.children.as_ref().map_or(0, |children| {
+            (children.capacity() * size_of::<Self>()) as u64
+                + children.iter().map(Self::heap_usage).sum::<u64>()
+        });
+        path + children
+    }
+
+    /// Serializes the tree below this entry as JSON.
+    ///
+    /// Each node is `{"name", "size", "allocated_size", "items", "is_dir"}`, plus a
+    /// `"children"` array for directories. The top node is named by its full path and the
+    /// others by their file name. Non-UTF-8 names are converted lossily.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        self.to_json_with(JsonOptions::default())
+    }
+
+    /// Like [`to_json`](Self::to_json), with percentages added or sizes left out as
+    /// `options` asks.
+    ///
+    /// With `percent_of`, every node gets a `"percent"` field: its apparent size as a
+    /// percentage of its parent's or of the top node's, which is always 100.
+    #[must_use]
+    pub fn to_json_with(&self, options: JsonOptions) -> String {
+        let mut out = String::new();
+        let context = JsonContext {
+            options,
+            parent_size: self.size,
+            root_size: self.size,
+        };
+        write_json(self, &self.path.to_string_lossy(), &context, &mut out);
+        out
+    }
+
+    /// Serializes the tree below this entry in the "folded stacks" format read by
+    /// `flamegraph.pl`, speedscope and similar tools.
+    ///
+    /// Each line is a path with its components joined by `;`, a space, and an apparent size
+    /// in bytes. Files and empty directories get a line each; a directory whose size is more
+    /// than its children's gets one for the remainder, so every frame adds up to its entry's
+    /// size. The first component is the top node's full path. Zero-sized lines are left out,
+    /// and `;` and line breaks inside names are replaced with `_`.
+    #[must_use]
+    pub fn to_folded_stacks(&self) -> String {
+        let mut out = String::new();
+        write_folded(self, &folded_frame(&self.path.to_string_lossy()), &mut out);
+        out
+    }
+}
+
+/// Appends the folded stack lines for `node`, whose frames so far are `stack`, to `out`.
+fn write_folded(node: &FileInfo, stack: &str, out: &mut String) {
+    let children = node.children.as_deref().unwrap_or(&[]);
+    let children_size: u64 = children.iter().map(|child| child.size).sum();
+    let own_size = node.size.saturating_sub(children_size);
+    if own_size > 0 {
+        let _ = writeln!(out, "{stack} {own_size}");
+    }
+    for child in children {
+        let name = child.path.file_name().map_or_else(
+            || child.path.to_string_lossy(),
+            |name| name.to_string_lossy(),
+        );
+        write_folded(child, &format!("{stack};{}", folded_frame(&name)), out);
+    }
+}
+
+/// Replaces the characters that would break a folded stack line.
+fn folded_frame(name: &str) -> String {
+    name.replace([';', '\n', '\r'], "_")
+}
+
+/// What the percentages in a JSON export are relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentOf {
+    /// The directory directly containing the entry, so siblings add up to 100.
+    Parent,
+    /// The top of the exported tree.
+    Root,
+}
+
+/// Options for [`FileInfo::to_json_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    pub percent_of: Option<PercentOf>, // Add a `percent` field to every node
+    pub percent_only: bool,            // Leave out `size` and `allocated_size`
+}
+
+/// What [`write_json`] needs to know about the nodes above the one being written.
+struct JsonContext {
+    options: JsonOptions,
+    parent_size: u64,
+    root_size: u64,
+}
+
+/// Appends the JSON for `node`, labelled `name`, to `out`.
+fn write_json(node: &FileInfo, name: &str, context: &JsonContext, out: &mut String) {
+    let _ = write!(out, "{{\"name\":{}", json::string(name));
+    if !context.options.percent_only {
+        let _ = write!(
+            out,
+            ",\"size\":{},\"allocated_size\":{}",
+            node.size, node.allocated_size
+        );
+    }
+    if let Some(percent_of) = context.options.percent_of {
+        let whole = match percent_of {
+            PercentOf::Parent => context.parent_size,
+            PercentOf::Root => context.root_size,
+        };
+        let _ = write!(out, ",\"percent\":{:.2}", percentage(node.size, whole));
+    }
+    let _ = write!(out, ",\"items\":{},\"is_dir\":{}", node.items, node.is_dir);
+    if let Some(children) = &node.children {
+        let child_context = JsonContext {
+            parent_size: node.size,
+            ..*context
+        };
+        out.push_str(",\"children\":[");
+        for (i, child) in children.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let child_name = child.path.file_name().map_or_else(
+                || child.path.to_string_lossy(),
+                |name| name.to_string_lossy(),
+            );
+            write_json(child, &child_name, &child_context, out);
+        }
+        out.push(']');
+    }
+    out.push('}');
+}
+
+/// Compares two nodes by path, type, item count, every size, owner and modification time,
+/// ignoring their children.
+///
+/// Use [`tree_eq`] to compare whole subtrees.
+impl PartialEq for FileInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+            && self.size == other.size
+            && self.is_dir == other.is_dir
+            && self.items == other.items
+            && self.allocated_size == other.allocated_size
+            && self.owner == other.owner
+            && self.modified == other.modified
+            && self.xattr_size == other.xattr_size
+    }
+}
+
+impl Eq for FileInfo {}
+
+/// Recursively compares two trees: every node must be equal and have its children in the same order.
+#[must_use]
+pub fn tree_eq(a: &FileInfo, b: &FileInfo) -> bool {
+    if a != b {
+        return false;
+    }
+    match (&a.children, &b.children) {
+        (None, None) => true,
+        (Some(a_children), Some(b_children)) => {
+            a_children.len() == b_children.len()
+                && a_children
+                    .iter()
+                    .zip(b_children)
+                    .all(|(a_child, b_child)| tree_eq(a_child, b_child))
         }
+        _ => false,
+    }
+}
+
+/// The order in which the children of a directory are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Largest first.
+    Size,
+    /// Alphabetical by file name.
+    Name { case_sensitive: bool },
+    /// Most slack (allocated beyond apparent size) first.
+    Slack,
+    /// Largest allocated size first.
+    Allocated,
+    /// Most entries contained at any depth first.
+    Items,
+    /// Most directories directly inside first.
+    Subdirs,
+    /// Largest exclusive size (files directly inside) first.
+    Exclusive,
+}
+
+impl SortOrder {
+    /// Compares two sibling entries according to this order.
+    #[must_use]
+    pub fn compare(self, a: &FileInfo, b: &FileInfo) -> Ordering {
+        match self {
+            Self::Size => b.size.cmp(&a.size).then_with(|| compare_names(a, b, false)),
+            Self::Name { case_sensitive } => compare_names(a, b, case_sensitive),
+            Self::Slack => b
+                .slack()
+                .cmp(&a.slack())
+                .then_with(|| compare_names(a, b, false)),
+            Self::Allocated => b
+                .allocated_size
+                .cmp(&a.allocated_size)
+                .then_with(|| compare_names(a, b, false)),
+            Self::Items => b
+                .contained_items()
+                .cmp(&a.contained_items())
+                .then_with(|| compare_names(a, b, false)),
+            Self::Subdirs => b
+                .subdirectory_count()
+                .cmp(&a.subdirectory_count())
+                .then_with(|| compare_names(a, b, false)),
+            Self::Exclusive => b
+                .exclusive_size()
+                .cmp(&a.exclusive_size())
+                .then_with(|| compare_names(a, b, false)),
+        }
+    }
+}
+
+/// A per-entry quantity that listings can be measured by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    /// Apparent size in bytes.
+    #[default]
+    Apparent,
+    /// Bytes allocated on disk.
+    Allocated,
+    /// Allocated bytes beyond the apparent size.
+    Slack,
+    /// Entries contained at any depth, leaving out the entry itself, so a file counts as 0.
+    Items,
+    /// Apparent size of a directory's own files, leaving out its subdirectories.
+    Exclusive,
+}
+
+impl Metric {
+    /// Returns this metric's value for `entry`.
+    #[must_use]
+    pub fn value(self, entry: &FileInfo) -> u64 {
+        match self {
+            Self::Apparent => entry.size,
+            Self::Allocated => entry.allocated_size,
+            Self::Slack => entry.slack(),
+            Self::Items => entry.contained_items(),
+            Self::Exclusive => entry.exclusive_size(),
+        }
+    }
+
+    /// Returns the value that the entries of `dir` are a percentage of.
+    ///
+    /// This is the metric's value for `dir`, except for exclusive sizes: the exclusive size
+    /// of a subdirectory is not part of its parent's, so those are measured against the
+    /// directory's full apparent size.
+    #[must_use]
+    pub fn whole(self, dir: &FileInfo) -> u64 {
+        match self {
+            Self::Exclusive => dir.size,
+            _ => self.value(dir),
+        }
+    }
+
+    /// Returns `true` if the metric is measured in bytes rather than counted.
+    #[must_use]
+    pub const fn is_size(self) -> bool {
+        !matches!(self, Self::Items)
+    }
+
+    /// Returns the order listing the largest values of this metric first.
+    #[must_use]
+    pub const fn sort_order(self) -> SortOrder {
+        match self {
+            Self::Apparent => SortOrder::Size,
+            Self::Allocated => SortOrder::Allocated,
+            Self::Slack => SortOrder::Slack,
+            Self::Items => SortOrder::Items,
+            Self::Exclusive => SortOrder::Exclusive,
+        }
+    }
+
+    /// Returns the metric after this one, wrapping around after the last.
+    #[must_use]
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Apparent => Self::Allocated,
+            Self::Allocated => Self::Slack,
+            Self::Slack => Self::Items,
+            Self::Items => Self::Exclusive,
+            Self::Exclusive => Self::Apparent,
+        }
+    }
+
+    /// Returns the name shown to users, e.g. `apparent size`.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Apparent => "apparent size",
+            Self::Allocated => "disk usage",
+            Self::Slack => "slack",
+            Self::Items => "items",
+            Self::Exclusive => "exclusive size",
+        }
+    }
+}
+
+/// Compares file names, optionally ignoring case. Case-insensitive comparison uses full
+/// Unicode lowercasing and falls back to the exact name so the order stays deterministic.
+fn compare_names(a: &FileInfo, b: &FileInfo, case_sensitive: bool) -> Ordering {
+    let a_name = a
+        .path
+        .file_name()
+        .unwrap_or(a.path.as_os_str())
+        .to_string_lossy();
+    let b_name = b
+        .path
+        .file_name()
+        .unwrap_or(b.path.as_os_str())
+        .to_string_lossy();
+    if case_sensitive {
+        a_name.cmp(&b_name)
     } else {
-        size = metadata.len();
+        a_name
+            .to_lowercase()
+            .cmp(&b_name.to_lowercase())
+            .then_with(|| a_name.cmp(&b_name))
+    }
+}
+
+/// Sorts the children of `node`, leaving their own children as they are.
+///
+/// Use this to sort only the directory being viewed; [`sort_children`] sorts the whole tree.
+pub fn sort_direct_children(node: &mut FileInfo, order: SortOrder) {
+    if let Some(children) = node.children.as_mut() {
+        children.sort_by(|a, b| order.compare(a, b));
+    }
+}
+
+/// Recursively sorts the children of `node` and all its descendants.
+pub fn sort_children(node: &mut FileInfo, order: SortOrder) {
+    if let Some(children) = node.children.as_mut() {
+        children.sort_by(|a, b| order.compare(a, b));
+        for child in children {
+            sort_children(child, order);
+        }
+    }
+}
+
+/// Returns `part` as a percentage of `whole`, clamped to `0.0..=100.0`.
+///
+/// A zero `whole` yields `0.0` rather than NaN. The ratio is computed in integer
+/// arithmetic to millionths of a percent, so sizes near `u64::MAX` stay exact.
+#[must_use]
+pub fn percentage(part: u64, whole: u64) -> f64 {
+    const SCALE: u128 = 100_000_000; // 100% in millionths of a percent
+
+    if whole == 0 {
+        return 0.0;
+    }
+    let scaled = (u128::from(part.min(whole)) * SCALE) / u128::from(whole);
+    // `scaled` is at most 100_000_000, which f64 represents exactly.
+    #[allow(clippy::cast_precision_loss)]
+    let millionths = scaled as f64;
+    millionths / 1_000_000.0
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)] // percentage() is exact to millionths of a percent
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentage_of_zero_is_zero() {
+        assert_eq!(percentage(0, 0), 0.0);
+        assert_eq!(percentage(5, 0), 0.0);
+    }
+
+    #[test]
+    fn percentage_of_the_whole_is_one_hundred() {
+        assert_eq!(percentage(1, 1), 100.0);
+        assert_eq!(percentage(4096, 4096), 100.0);
+        assert_eq!(percentage(u64::MAX, u64::MAX), 100.0);
+    }
+
+    #[test]
+    fn percentage_stays_exact_near_u64_max() {
+        assert_eq!(percentage(1 << 63, u64::MAX), 50.0);
+        assert_eq!(percentage(1 << 62, u64::MAX), 25.0);
+        assert_eq!(percentage(u64::MAX - 1, u64::MAX), 99.999_999);
+        assert_eq!(percentage(1, u64::MAX), 0.0);
+        assert_eq!(percentage(u64::MAX, 1), 100.0);
+    }
+
+    #[test]
+    fn files_lists_every_file_in_tree_order() {
+        let file = |path: &str| FileInfo::new(path.into(), 10, false, 1);
+        let root = FileInfo::new_dir("/root".into(), 0)
+            .with_child(
+                FileInfo::new_dir("/root/a".into(), 0)
+                    .with_child(file("/root/a/1"))
+                    .with_child(FileInfo::new_dir("/root/a/empty".into(), 0))
+                    .with_child(file("/root/a/2")),
+            )
+            .with_child(FileInfo::new("/root/unscanned".into(), 0, true, 1))
+            .with_child(file("/root/b"));
+
+        let paths: Vec<_> = root
+            .files()
+            .map(|entry| entry.path.to_str().unwrap())
+            .collect();
+        assert_eq!(paths, ["/root/a/1", "/root/a/2", "/root/b"]);
+        assert_eq!(file("/file").files().count(), 1);
+    }
+
+    #[test]
+    fn tree_eq_requires_children_in_the_same_order() {
+        let file = |path: &str| FileInfo::new(path.into(), 10, false, 1);
+        let tree = |first: &str, second: &str| {
+            FileInfo::new_dir("/root".into(), 0)
+                .with_child(file(first))
+                .with_child(file(second))
+        };
+        let a = tree("/root/a", "/root/b");
+        assert!(tree_eq(&a, &tree("/root/a", "/root/b")));
+        assert!(!tree_eq(&a, &tree("/root/b", "/root/a")));
+        // The nodes themselves still compare equal, since `==` ignores children.
+        assert_eq!(a, tree("/root/b", "/root/a"));
     }
 
-    let mut file_info = FileInfo::new(path.to_path_buf(), size, is_dir, items);
-    if is_dir {
-        file_info.children = Some(children);
+    #[test]
+    fn tree_eq_tells_unscanned_from_empty_directories() {
+        let unscanned = FileInfo::new("/root".into(), 0, true, 1);
+        let empty = FileInfo {
+            children: Some(Vec::new()),
+            ..unscanned.clone()
+        };
+        assert_eq!(unscanned, empty);
+        assert!(!tree_eq(&unscanned, &empty));
+        assert!(!tree_eq(&empty, &unscanned));
+        assert!(tree_eq(&empty, &FileInfo::new_dir("/root".into(), 0)));
     }
 
-    Ok(file_info)
+    #[test]
+    fn tree_eq_compares_nested_sizes() {
+        let tree = |size| {
+            FileInfo::new_dir("/root".into(), 0).with_child(
+                FileInfo::new_dir("/root/dir".into(), 0).with_child(FileInfo::new(
+                    "/root/dir/file".into(),
+                    size,
+                    false,
+                    1,
+                )),
+            )
+        };
+        assert!(tree_eq(&tree(5), &tree(5)));
+        let mut changed = tree(5);
+        changed.children.as_mut().unwrap()[0]
+            .children
+            .as_mut()
+            .unwrap()[0]
+            .size = 6;
+        assert!(!tree_eq(&tree(5), &changed));
+    }
+
+    #[test]
+    fn items_metric_counts_contents_only() {
+        let file = |path: &str| FileInfo::new(path.into(), 10, false, 1);
+        let mut root = FileInfo::new_dir("/root".into(), 0)
+            .with_child(file("/root/big"))
+            .with_child(FileInfo::new_dir("/root/empty".into(), 0))
+            .with_child(
+                FileInfo::new_dir("/root/full".into(), 0)
+                    .with_child(file("/root/full/a"))
+                    .with_child(file("/root/full/b")),
+            );
+        sort_direct_children(&mut root, Metric::Items.sort_order());
+
+        let values: Vec<_> = root
+            .children
+            .as_deref()
+            .unwrap()
+            .iter()
+            .map(|entry| (entry.path.to_str().unwrap(), Metric::Items.value(entry)))
+            .collect();
+        assert_eq!(
+            values,
+            [("/root/full", 2), ("/root/big", 0), ("/root/empty", 0)]
+        );
+        assert_eq!(Metric::Items.whole(&root), 5);
+    }
 }
@@ -0,0 +1,174 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// A mounted filesystem as listed by the operating system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mount {
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+}
+
+/// Filesystem types whose contents are synthesized by the kernel rather than stored on disk.
+const PSEUDO_FS_TYPES: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Well-known pseudo filesystem locations, used when the mount table cannot be read.
+const PSEUDO_FS_PATHS: &[&str] = &["/proc", "/sys", "/dev"];
+
+/// Returns `true` if `fs_type` names a pseudo filesystem such as `proc` or `sysfs`.
+#[must_use]
+pub fn is_pseudo_fs_type(fs_type: &str) -> bool {
+    PSEUDO_FS_TYPES.contains(&fs_type)
+}
+
+/// Reads the mount table from `/proc/self/mountinfo`.
+///
+/// # Errors
+///
+/// Returns an error if the mount table cannot be read, e.g. on platforms without procfs.
+pub fn read_mounts() -> io::Result<Vec<Mount>> {
+    let contents = fs::read_to_string("/proc/self/mountinfo")?;
+    Ok(contents.lines().filter_map(parse_mountinfo_line).collect())
+}
+
+/// Returns the mount points of all pseudo filesystems.
+///
+/// Falls back to a list of well-known paths if the mount table is unavailable.
+#[must_use]
+pub fn pseudo_filesystem_paths() -> Vec<PathBuf> {
+    read_mounts().map_or_else(
+        |_| PSEUDO_FS_PATHS.iter().map(PathBuf::from).collect(),
+        |mounts| {
+            mounts
+                .into_iter()
+                .filter(|mount| is_pseudo_fs_type(&mount.fs_type))
+                .map(|mount| mount.mount_point)
+                .collect()
+        },
+    )
+}
+
+/// Parses one line of `/proc/self/mountinfo`.
+///
+/// The mount point is the fifth field and the filesystem type follows the ` - ` separator.
+fn parse_mountinfo_line(line: &str) -> Option<Mount> {
+    let (fields, rest) = line.split_once(" - ")?;
+    let mount_point = fields.split(' ').nth(4)?;
+    let fs_type = rest.split(' ').next()?;
+    Some(Mount {
+        mount_point: PathBuf::from(unescape_mount_path(mount_point)),
+        fs_type: fs_type.to_string(),
+    })
+}
+
+/// Decodes the octal escapes (`\040` for space and so on) used in the mount table.
+fn unescape_mount_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            if let Some(byte) = path
+                .get(i + 1..i + 4)
+                .and_then(|octal| u8::from_str_radix(octal, 8).ok())
+            {
+                decoded.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Capacity of the filesystem holding a path, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FsUsage {
+    pub total: u64,
+    pub free: u64,      // Free blocks, including those reserved for root
+    pub available: u64, // Free blocks usable by unprivileged users
+}
+
+impl FsUsage {
+    /// Returns the number of bytes in use.
+    #[must_use]
+    pub const fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    /// Returns how full the filesystem is, computed like `df`: used / (used + available).
+    #[must_use]
+    pub fn percent_used(&self) -> f64 {
+        crate::percentage(self.used(), self.used() + self.available)
+    }
+
+    /// Returns the usage projected after `bytes` have been freed.
+    #[must_use]
+    pub const fn after_freeing(&self, bytes: u64) -> Self {
+        let freed = if bytes < self.used() {
+            bytes
+        } else {
+            self.used()
+        };
+        Self {
+            total: self.total,
+            free: self.free + freed,
+            available: self.available + freed,
+        }
+    }
+}
+
+/// Queries the capacity of the filesystem containing `path` via `statvfs`.
+///
+/// # Errors
+///
+/// Returns an error if `statvfs` fails for `path`.
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)] // The field types differ between platforms
+pub fn filesystem_usage(path: &std::path::Path) -> io::Result<FsUsage> {
+    let stats = nix::sys::statvfs::statvfs(path).map_err(io::Error::from)?;
+    let fragment_size = u64::from(stats.fragment_size());
+    Ok(FsUsage {
+        total: u64::from(stats.blocks()) * fragment_size,
+        free: u64::from(stats.blocks_free()) * fragment_size,
+        available: u64::from(stats.blocks_available()) * fragment_size,
+    })
+}
+
+/// Queries the capacity of the filesystem containing `path`.
+///
+/// # Errors
+///
+/// Always fails: filesystem capacity is only queried on Unix.
+#[cfg(not(unix))]
+pub fn filesystem_usage(_path: &std::path::Path) -> io::Result<FsUsage> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "filesystem capacity is not available on this platform",
+    ))
+}
@@ -0,0 +1,100 @@
+use crate::FileInfo;
+use std::cmp::Reverse;
+
+/// Returns the entries below `root` owned by `uid`, largest first.
+///
+/// Only the topmost owned entry along each path is listed. The contents of an owned
+/// directory are not listed again, so the sizes do not overlap. `root` itself is never
+/// listed, since it is usually owned by whoever scans it.
+#[must_use]
+pub fn owned_by(root: &FileInfo, uid: u32) -> Vec<&FileInfo> {
+    let mut owned = Vec::new();
+    for child in root.children.as_deref().unwrap_or(&[]) {
+        collect_owned(child, uid, &mut owned);
+    }
+    owned.sort_by_key(|entry| Reverse(entry.size));
+    owned
+}
+
+/// Adds the topmost entries at or below `node` that are owned by `uid`.
+fn collect_owned<'a>(node: &'a FileInfo, uid: u32, owned: &mut Vec<&'a FileInfo>) {
+    if node.owner == Some(uid) {
+        owned.push(node);
+        return;
+    }
+    for child in node.children.as_deref().unwrap_or(&[]) {
+        collect_owned(child, uid, owned);
+    }
+}
+
+/// Returns the login name of the user with `uid`, if it can be looked up.
+#[cfg(unix)]
+#[must_use]
+pub fn user_name(uid: u32) -> Option<String> {
+    use nix::unistd::{Uid, User};
+    User::from_uid(Uid::from_raw(uid))
+        .ok()
+        .flatten()
+        .map(|user| user.name)
+}
+
+/// Returns the login name of the user with `uid`, if it can be looked up.
+///
+/// User IDs do not exist on this platform.
+#[cfg(not(unix))]
+#[must_use]
+pub const fn user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(path: &str, size: u64, owner: u32, children: Option<Vec<FileInfo>>) -> FileInfo {
+        FileInfo {
+            owner: Some(owner),
+            children,
+            ..FileInfo::new(path.into(), size, true, 1)
+        }
+    }
+
+    #[test]
+    fn lists_children_when_the_root_has_the_same_owner() {
+        let root = owned(
+            "/root",
+            30,
+            1000,
+            Some(vec![
+                owned("/root/small", 10, 1000, None),
+                owned("/root/large", 20, 1000, None),
+                owned("/root/other", 5, 0, None),
+            ]),
+        );
+
+        let paths: Vec<_> = owned_by(&root, 1000)
+            .iter()
+            .map(|entry| entry.path.to_str().unwrap())
+            .collect();
+        assert_eq!(paths, ["/root/large", "/root/small"]);
+    }
+
+    #[test]
+    fn does_not_list_the_contents_of_an_owned_directory() {
+        let root = owned(
+            "/root",
+            30,
+            0,
+            Some(vec![owned(
+                "/root/home",
+                30,
+                1000,
+                Some(vec![owned("/root/home/file", 30, 1000, None)]),
+            )]),
+        );
+
+        let owned = owned_by(&root, 1000);
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].path.to_str(), Some("/root/home"));
+    }
+}
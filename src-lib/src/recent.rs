@@ -0,0 +1,72 @@
+use crate::FileInfo;
+use std::cmp::Reverse;
+use std::time::SystemTime;
+
+/// Returns up to `limit` files below `root`, most recently modified first.
+///
+/// Directories are left out, since their modification time only says that an entry was
+/// added or removed, as are files whose modification time is unknown.
+#[must_use]
+pub fn recently_modified(root: &FileInfo, limit: usize) -> Vec<&FileInfo> {
+    let mut files: Vec<_> = root
+        .files()
+        .filter(|file| file.modified.is_some())
+        .collect();
+    files.sort_by_key(|entry| Reverse(entry.modified));
+    files.truncate(limit);
+    files
+}
+
+/// Returns a copy of the tree at `root` holding only what was modified at or after `cutoff`.
+///
+/// An entry's own size counts when its modification time falls in the window; for a
+/// directory that is its entry, not its contents. Directories are kept when they changed
+/// themselves or hold something that did, and their totals are recomputed from what is
+/// kept, so each one shows how much of it is recent. The top node is always returned, even
+/// when nothing below it matches.
+#[must_use]
+pub fn modified_since(root: &FileInfo, cutoff: SystemTime) -> FileInfo {
+    prune(root, cutoff).unwrap_or_else(|| FileInfo {
+        children: root.is_dir.then(Vec::new),
+        ..FileInfo::new(root.path.clone(), 0, root.is_dir, 1)
+    })
+}
+
+/// Returns the recent part of `node`, or `None` if nothing at or below it is recent.
+fn prune(node: &FileInfo, cutoff: SystemTime) -> Option<FileInfo> {
+    let recent = node.modified.is_some_and(|modified| modified >= cutoff);
+    let Some(children) = &node.children else {
+        return recent.then(|| node.clone());
+    };
+
+    // A directory's own contribution is whatever its totals hold beyond its children's.
+    let (children_size, children_allocated, children_xattr) =
+        children
+            .iter()
+            .fold((0, 0, 0), |(size, allocated, xattr), child| {
+                (
+                    size + child.size,
+                    allocated + child.allocated_size,
+                    xattr + child.xattr_size,
+                )
+            });
+    let (own_size, own_allocated, own_xattr) = if recent {
+        (
+            node.size.saturating_sub(children_size),
+            node.allocated_size.saturating_sub(children_allocated),
+            node.xattr_size.saturating_sub(children_xattr),
+        )
+    } else {
+        (0, 0, 0)
+    };
+
+    let mut pruned = FileInfo::new_dir(node.path.clone(), own_size);
+    pruned.allocated_size = own_allocated;
+    pruned.xattr_size = own_xattr;
+    pruned.owner = node.owner;
+    pruned.modified = node.modified;
+    for child in children.iter().filter_map(|child| prune(child, cutoff)) {
+        pruned.add_child(child);
+    }
+    (recent || pruned.items > 1).then_some(pruned)
+}
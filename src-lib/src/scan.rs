@@ -0,0 +1,760 @@
+use crate::dir_handle::{DirHandle, EntryStat};
+use crate::exclude::ExcludeSet;
+use crate::{mounts, xattrs, FileInfo};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Options controlling how a directory tree is scanned.
+#[derive(Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Paths below the scan root that are neither descended into nor counted.
+    pub skip_paths: Vec<PathBuf>,
+    /// Simulated allocation unit. When set, an entry's allocated size is its apparent size
+    /// rounded up to a multiple of this, instead of the blocks the filesystem reports.
+    pub block_size: Option<NonZeroU64>,
+    /// Patterns for entries below the scan root that are neither descended into nor counted.
+    pub exclude: ExcludeSet,
+    /// Stops the scan once set to `true`, e.g. from another thread when a time limit is up.
+    /// The entries read so far are kept and the [`Scan`] is marked incomplete.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Whether extended attributes are read, and whether they count towards sizes.
+    pub xattrs: Xattrs,
+}
+
+/// How a scan treats extended attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Xattrs {
+    /// Not read, so `xattr_size` stays 0.
+    #[default]
+    Skip,
+    /// Summed into [`FileInfo::xattr_size`], apart from the other sizes.
+    Separate,
+    /// Summed into `xattr_size` and added to the apparent size as well, so totals include
+    /// them. Allocated sizes are unchanged, since attributes usually live in the inode.
+    InTotals,
+}
+
+impl ScanOptions {
+    /// Skips the mount points of pseudo filesystems such as `/proc` and `/sys`.
+    pub fn skip_pseudo_filesystems(&mut self) {
+        self.skip_paths.extend(mounts::pseudo_filesystem_paths());
+    }
+
+    /// Skips the mount points of filesystems whose type `is_skipped_type` returns `true` for,
+    /// such as `nfs` or `tmpfs`, leaving out everything stored on them.
+    ///
+    /// Only mounts below the scan root are affected; the filesystem holding the root itself
+    /// is always scanned. Where several filesystems are mounted on the same path, the one
+    /// mounted last is the one that counts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mount table cannot be read.
+    pub fn skip_filesystems(&mut self, is_skipped_type: impl Fn(&str) -> bool) -> io::Result<()> {
+        let mut visible: HashMap<PathBuf, String> = HashMap::new();
+        for mount in mounts::read_mounts()? {
+            visible.insert(mount.mount_point, mount.fs_type);
+        }
+        self.skip_paths.extend(
+            visible
+                .into_iter()
+                .filter(|(_, fs_type)| is_skipped_type(fs_type))
+                .map(|(mount_point, _)| mount_point),
+        );
+        Ok(())
+    }
+
+    /// Returns `true` if `path` should be left out of the scan.
+    fn is_skipped(&self, path: &Path) -> bool {
+        self.skip_paths.iter().any(|skip| skip == path) || self.exclude.is_match(path)
+    }
+}
+
+/// Why an entry could not be scanned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ScanErrorKind {
+    /// The entry or its directory is not readable by the current user.
+    PermissionDenied,
+    /// The entry is locked or held open exclusively by another process.
+    InUse,
+    /// Any other I/O failure.
+    Other,
+}
+
+impl ScanErrorKind {
+    /// Classifies an I/O error, recognising the platform's "in use" and "locked" codes.
+    #[must_use]
+    pub fn of(error: &io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            io::ErrorKind::ResourceBusy | io::ErrorKind::ExecutableFileBusy => Self::InUse,
+            _ if is_sharing_violation(error) => Self::InUse,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for ScanErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PermissionDenied => "permission denied",
+            Self::InUse => "in use / locked",
+            Self::Other => "other error",
+        })
+    }
+}
+
+/// Returns `true` for Windows' `ERROR_SHARING_VIOLATION` and `ERROR_LOCK_VIOLATION`.
+#[cfg(windows)]
+fn is_sharing_violation(error: &io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(32 | 33))
+}
+
+/// Returns `true` for Windows' `ERROR_SHARING_VIOLATION` and `ERROR_LOCK_VIOLATION`.
+#[cfg(not(windows))]
+const fn is_sharing_violation(_error: &io::Error) -> bool {
+    false
+}
+
+/// An entry that was skipped because it could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: ScanErrorKind,
+    pub message: String,
+}
+
+impl ScanError {
+    fn new(path: PathBuf, error: &io::Error) -> Self {
+        Self {
+            path,
+            kind: ScanErrorKind::of(error),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
+
+/// A scanned tree together with the entries that could not be read.
+#[derive(Debug, Clone)]
+pub struct Scan {
+    pub root: FileInfo,
+    pub errors: Vec<ScanError>,
+    pub incomplete: bool, // Cancelled before every entry was read
+}
+
+impl Scan {
+    /// Returns how many errors are of `kind`.
+    #[must_use]
+    pub fn error_count(&self, kind: ScanErrorKind) -> usize {
+        self.errors
+            .iter()
+            .filter(|error| error.kind == kind)
+            .count()
+    }
+}
+
+/// Recursively traverses a directory and calculates the size of each file and directory.
+///
+/// # Errors
+///
+/// Returns an error if the metadata of `path` itself cannot be read, or if `path` is a
+/// directory that cannot be listed. Failures on descendants are reported as warnings and
+/// the offending entry is skipped.
+pub fn traverse_directory(path: &Path) -> io::Result<FileInfo> {
+    traverse_directory_with(path, &ScanOptions::default())
+}
+
+/// Like [`traverse_directory`], but applies `options` to every entry below `path`.
+///
+/// # Errors
+///
+/// Returns an error under the same conditions as [`traverse_directory`].
+pub fn traverse_directory_with(path: &Path, options: &ScanOptions) -> io::Result<FileInfo> {
+    let scan = scan(path, options)?;
+    for error in &scan.errors {
+        eprintln!("Warning: Could not traverse {error}");
+    }
+    Ok(scan.root)
+}
+
+/// Scans the tree at `path`, collecting entries that could not be read instead of printing them.
+///
+/// Sizes follow `du`: `size` totals match `du --apparent-size -b` and `allocated_size`
+/// totals match `du -B1`. Symbolic links are not followed and count as their own (small)
+/// size, directories include the size of the directory entry itself, and a file with
+/// several hard links inside the tree is counted once, at the first link encountered.
+/// Later links to the same file appear with a size of zero.
+///
+/// # Errors
+///
+/// Returns an error under the same conditions as [`traverse_directory`].
+pub fn scan(path: &Path, options: &ScanOptions) -> io::Result<Scan> {
+    scan_with_progress(path, options, &mut |_| {})
+}
+
+/// How far a running scan has got, as reported to a progress callback.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    pub items: u64,     // Entries scanned so far, including the one at `path`
+    pub path: &'a Path, // Entry being scanned
+}
+
+/// Like [`scan`], but calls `progress` for every entry as it is scanned.
+///
+/// # Errors
+///
+/// Returns an error under the same conditions as [`traverse_directory`].
+pub fn scan_with_progress(
+    path: &Path,
+    options: &ScanOptions,
+    progress: &mut dyn FnMut(&Progress<'_>),
+) -> io::Result<Scan> {
+    let mut state = ScanState {
+        progress: Some(progress),
+        ..ScanState::default()
+    };
+    let root = traverse(path, options, &mut state)?;
+    Ok(Scan {
+        root,
+        errors: state.errors,
+        incomplete: state.cancelled,
+    })
+}
+
+/// Bookkeeping carried through a single scan.
+#[derive(Default)]
+struct ScanState<'a> {
+    errors: Vec<ScanError>,
+    seen_inodes: HashSet<(u64, u64)>, // (device, inode) of hard-linked files already counted
+    items_scanned: u64,
+    progress: Option<&'a mut dyn FnMut(&Progress<'_>)>,
+    cancelled: bool, // The cancel flag was seen set; no more entries are read
+}
+
+impl ScanState<'_> {
+    /// Counts the entry at `path` and reports it to the progress callback, if any.
+    fn report(&mut self, path: &Path) {
+        self.items_scanned += 1;
+        if let Some(progress) = self.progress.as_mut() {
+            progress(&Progress {
+                items: self.items_scanned,
+                path,
+            });
+        }
+    }
+
+    /// Returns `true` once the scan has been cancelled through `options`.
+    fn is_cancelled(&mut self, options: &ScanOptions) -> bool {
+        if !self.cancelled {
+            self.cancelled = options
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+        }
+        self.cancelled
+    }
+
+    /// Returns the apparent, allocated and extended attribute size that the entry at `path`
+    /// described by `stat` contributes to the totals.
+    ///
+    /// With [`Xattrs::InTotals`] the apparent size includes the attributes.
+    fn sizes(&mut self, path: &Path, stat: &EntryStat, options: &ScanOptions) -> (u64, u64, u64) {
+        if self.is_repeated_link(stat) {
+            return (0, 0, 0);
+        }
+        let allocated_size = options.block_size.map_or(stat.allocated, |block| {
+            stat.len.div_ceil(block.get()).saturating_mul(block.get())
+        });
+        match options.xattrs {
+            Xattrs::Skip => (stat.len, allocated_size, 0),
+            Xattrs::Separate => (stat.len, allocated_size, xattrs::size_of(path)),
+            Xattrs::InTotals => {
+                let xattr_size = xattrs::size_of(path);
+                (stat.len + xattr_size, allocated_size, xattr_size)
+            }
+        }
+    }
+
+    /// Returns `true` if `stat` describes a hard link to a file that was already counted.
+    fn is_repeated_link(&mut self, stat: &EntryStat) -> bool {
+        !stat.is_dir && stat.links > 1 && !self.seen_inodes.insert(stat.id)
+    }
+}
+
+/// Builds the `FileInfo` tree for `path`, recording failures below it in `state`.
+fn traverse(path: &Path, options: &ScanOptions, state: &mut ScanState) -> io::Result<FileInfo> {
+    let stat = EntryStat::of_path(path)?;
+    let dir = stat.is_dir.then(|| DirHandle::open(path)).transpose()?;
+    traverse_entry(path, &stat, dir.as_ref(), options, state)
+}
+
+/// Builds the `FileInfo` tree for the entry `name` in `parent`, whose full path is `path`.
+fn traverse_child(
+    parent: &DirHandle,
+    name: &OsStr,
+    path: &Path,
+    options: &ScanOptions,
+    state: &mut ScanState,
+) -> io::Result<FileInfo> {
+    let stat = parent.stat_child(name, path)?;
+    let dir = stat
+        .is_dir
+        .then(|| parent.open_child(name, path))
+        .transpose()?;
+    traverse_entry(path, &stat, dir.as_ref(), options, state)
+}
+
+/// Builds the `FileInfo` tree for the entry at `path` described by `stat`, reading its
+/// contents through `dir` if it is a directory.
+fn traverse_entry(
+    path: &Path,
+    stat: &EntryStat,
+    dir: Option<&DirHandle>,
+    options: &ScanOptions,
+    state: &mut ScanState,
+) -> io::Result<FileInfo> {
+    state.report(path);
+    let (mut size, mut allocated_size, mut xattr_size) = state.sizes(path, stat, options);
+    let mut items = 1; // Count the current item
+    let mut children = Vec::new();
+
+    if let Some(dir) = dir {
+        for name in dir.names()? {
+            if state.is_cancelled(options) {
+                break;
+            }
+            let child_path = path.join(&name);
+            if options.is_skipped(&child_path) {
+                continue;
+            }
+
+            match traverse_child(dir, &name, &child_path, options, state) {
+                Ok(child_info) => {
+                    size += child_info.size;
+                    allocated_size += child_info.allocated_size;
+                    xattr_size += child_info.xattr_size;
+                    items += child_info.items;
+                    children.push(child_info);
+                }
+                Err(e) => state.errors.push(ScanError::new(child_path, &e)),
+            }
+        }
+    }
+
+    let mut file_info = FileInfo::new(path.to_path_buf(), size, stat.is_dir, items);
+    file_info.allocated_size = allocated_size;
+    file_info.xattr_size = xattr_size;
+    file_info.owner = stat.owner;
+    file_info.modified = stat.modified;
+    if stat.is_dir {
+        file_info.children = Some(children);
+    }
+
+    Ok(file_info)
+}
+
+/// Aggregate counts for a directory tree, computed without keeping the tree in memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub size: u64,
+    pub allocated_size: u64,
+    pub items: u64, // Files and directories, including the root
+    pub files: u64, // Non-directory entries only
+}
+
+/// Recursively sums sizes and item counts below `path` without building a `FileInfo` tree.
+///
+/// This is the fast path for callers that only need the totals, such as `--count-only`.
+/// Sizes are counted the same way as in [`scan`].
+///
+/// # Errors
+///
+/// Returns an error under the same conditions as [`traverse_directory`].
+pub fn scan_totals(path: &Path) -> io::Result<Totals> {
+    scan_totals_with(path, &ScanOptions::default())
+}
+
+/// Like [`scan_totals`], but applies `options` to every entry below `path`.
+///
+/// Once `options.cancel` is set, no more entries are read and the totals so far are
+/// returned.
+///
+/// # Errors
+///
+/// Returns an error under the same conditions as [`traverse_directory`].
+pub fn scan_totals_with(path: &Path, options: &ScanOptions) -> io::Result<Totals> {
+    let mut state = ScanState::default();
+    let totals = sum_totals(path, options, &mut state)?;
+    for error in &state.errors {
+        eprintln!("Warning: Could not traverse {error}");
+    }
+    Ok(totals)
+}
+
+/// Sums `path` and the entries directly inside it, without descending into subdirectories.
+///
+/// Each direct child counts only its own size: a subdirectory contributes its directory entry,
+/// not its contents. This is close to `du -S`, which leaves subdirectories out entirely, and
+/// reads a single directory, so it is much faster than [`scan_totals_with`] on a deep tree.
+///
+/// # Errors
+///
+/// Returns an error if `path` cannot be read. Unreadable children are reported as warnings.
+pub fn scan_direct_totals(path: &Path, options: &ScanOptions) -> io::Result<Totals> {
+    let mut state = ScanState::default();
+    let stat = EntryStat::of_path(path)?;
+    let (size, allocated_size, _) = state.sizes(path, &stat, options);
+    let mut totals = Totals {
+        size,
+        allocated_size,
+        items: 1, // Count the current item
+        files: u64::from(!stat.is_dir),
+    };
+    if stat.is_dir {
+        let dir = DirHandle::open(path)?;
+        for name in dir.names()? {
+            let child_path = path.join(&name);
+            if options.is_skipped(&child_path) {
+                continue;
+            }
+
+            match dir.stat_child(&name, &child_path) {
+                Ok(child) => {
+                    let (size, allocated_size, _) = state.sizes(&child_path, &child, options);
+                    totals.size += size;
+                    totals.allocated_size += allocated_size;
+                    totals.items += 1;
+                    totals.files += u64::from(!child.is_dir);
+                }
+                Err(e) => state.errors.push(ScanError::new(child_path, &e)),
+            }
+        }
+    }
+    for error in &state.errors {
+        eprintln!("Warning: Could not traverse {error}");
+    }
+    Ok(totals)
+}
+
+/// Sums the totals for `path`, recording failures below it in `state`.
+fn sum_totals(path: &Path, options: &ScanOptions, state: &mut ScanState) -> io::Result<Totals> {
+    let stat = EntryStat::of_path(path)?;
+    let dir = stat.is_dir.then(|| DirHandle::open(path)).transpose()?;
+    sum_entry_totals(path, &stat, dir.as_ref(), options, state)
+}
+
+/// Sums the totals for the entry at `path` described by `stat`, reading its contents
+/// through `dir` if it is a directory.
+fn sum_entry_totals(
+    path: &Path,
+    stat: &EntryStat,
+    dir: Option<&DirHandle>,
+    options: &ScanOptions,
+    state: &mut ScanState,
+) -> io::Result<Totals> {
+    let (size, allocated_size, _) = state.sizes(path, stat, options);
+    let Some(dir) = dir else {
+        return Ok(Totals {
+            size,
+            allocated_size,
+            items: 1,
+            files: 1,
+        });
+    };
+
+    let mut totals = Totals {
+        size,
+        allocated_size,
+        items: 1, // Count the current item
+        files: 0,
+    };
+    for name in dir.names()? {
+        if state.is_cancelled(options) {
+            break;
+        }
+        let child_path = path.join(&name);
+        if options.is_skipped(&child_path) {
+            continue;
+        }
+
+        let child = dir.stat_child(&name, &child_path).and_then(|stat| {
+            let child_dir = stat
+                .is_dir
+                .then(|| dir.open_child(&name, &child_path))
+                .transpose()?;
+            sum_entry_totals(&child_path, &stat, child_dir.as_ref(), options, state)
+        });
+        match child {
+            Ok(child) => {
+                totals.size += child.size;
+                totals.allocated_size += child.allocated_size;
+                totals.items += child.items;
+                totals.files += child.files;
+            }
+            Err(e) => state.errors.push(ScanError::new(child_path, &e)),
+        }
+    }
+
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    /// Creates an empty directory for one test, removing any left over from an earlier run.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("rsdu-scan-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn busy_errors_are_classified_as_in_use() {
+        for kind in [
+            io::ErrorKind::ResourceBusy,
+            io::ErrorKind::ExecutableFileBusy,
+        ] {
+            assert_eq!(
+                ScanErrorKind::of(&io::Error::from(kind)),
+                ScanErrorKind::InUse
+            );
+        }
+        assert_eq!(
+            ScanErrorKind::of(&io::Error::from(io::ErrorKind::PermissionDenied)),
+            ScanErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            ScanErrorKind::of(&io::Error::from(io::ErrorKind::NotFound)),
+            ScanErrorKind::Other
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn errno_codes_are_classified() {
+        use nix::errno::Errno;
+        let of = |errno: Errno| ScanErrorKind::of(&io::Error::from_raw_os_error(errno as i32));
+        assert_eq!(of(Errno::EBUSY), ScanErrorKind::InUse);
+        assert_eq!(of(Errno::ETXTBSY), ScanErrorKind::InUse);
+        assert_eq!(of(Errno::EACCES), ScanErrorKind::PermissionDenied);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn sharing_and_lock_violations_are_classified_as_in_use() {
+        for code in [32, 33] {
+            let error = io::Error::from_raw_os_error(code);
+            assert_eq!(ScanErrorKind::of(&error), ScanErrorKind::InUse);
+        }
+        let access_denied = io::Error::from_raw_os_error(5);
+        assert_eq!(
+            ScanErrorKind::of(&access_denied),
+            ScanErrorKind::PermissionDenied
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scans_paths_longer_than_path_max() {
+        use nix::fcntl::{openat, OFlag};
+        use nix::sys::stat::{mkdirat, Mode};
+        use std::io::Write;
+
+        const DEPTH: usize = 30;
+        let root = scratch_dir("deep");
+        let name = "d".repeat(200);
+
+        // Build the tree one level at a time relative to the parent, since the full path
+        // is too long to create in one go.
+        let mut dir = openat(
+            nix::fcntl::AT_FDCWD,
+            &root,
+            OFlag::O_RDONLY | OFlag::O_DIRECTORY,
+            Mode::empty(),
+        )
+        .unwrap();
+        for _ in 0..DEPTH {
+            mkdirat(&dir, name.as_str(), Mode::from_bits_truncate(0o755)).unwrap();
+            dir = openat(
+                &dir,
+                name.as_str(),
+                OFlag::O_RDONLY | OFlag::O_DIRECTORY,
+                Mode::empty(),
+            )
+            .unwrap();
+        }
+        let file = openat(
+            &dir,
+            "file",
+            OFlag::O_WRONLY | OFlag::O_CREAT,
+            Mode::from_bits_truncate(0o644),
+        )
+        .unwrap();
+        fs::File::from(file).write_all(b"hello").unwrap();
+
+        let scan = scan(&root, &ScanOptions::default()).unwrap();
+        assert!(scan.errors.is_empty(), "{:?}", scan.errors);
+        assert_eq!(scan.root.items, DEPTH as u64 + 2);
+
+        let mut deepest = &scan.root;
+        while let Some(child) = deepest.children.as_deref().and_then(<[FileInfo]>::first) {
+            deepest = child;
+        }
+        assert!(deepest.path.as_os_str().len() > 4096);
+        assert_eq!(deepest.size, 5);
+
+        let totals = scan_totals_with(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(totals.items, DEPTH as u64 + 2);
+        assert_eq!(totals.size, scan.root.size);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn empty_directory_contains_no_items() {
+        let root = scratch_dir("empty");
+        fs::create_dir(root.join("empty")).unwrap();
+        let scan = scan(&root, &ScanOptions::default()).unwrap();
+        let empty = &scan.root.children.as_deref().unwrap()[0];
+        assert_eq!(empty.contained_items(), 0);
+        assert_eq!(scan.root.contained_items(), 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extended_attributes_are_summed_when_asked() {
+        let root = scratch_dir("xattr");
+        let file = root.join("file");
+        fs::write(&file, b"hello").unwrap();
+        if xattr::set(&file, "user.rsdu", b"0123456789").is_err() {
+            eprintln!("skipping: the temporary directory has no user extended attributes");
+            fs::remove_dir_all(&root).unwrap();
+            return;
+        }
+        let with = |xattrs| {
+            let options = ScanOptions {
+                xattrs,
+                ..ScanOptions::default()
+            };
+            scan(&root, &options).unwrap().root
+        };
+        let attribute = ("user.rsdu".len() + 10) as u64;
+
+        let skipped = with(Xattrs::Skip);
+        assert_eq!(skipped.xattr_size, 0);
+
+        let separate = with(Xattrs::Separate);
+        assert!(separate.xattr_size >= attribute);
+        assert_eq!(
+            separate.children.as_deref().unwrap()[0].xattr_size,
+            attribute
+        );
+        assert_eq!(separate.size, skipped.size);
+
+        let in_totals = with(Xattrs::InTotals);
+        assert_eq!(in_totals.size, skipped.size + in_totals.xattr_size);
+        assert_eq!(in_totals.allocated_size, skipped.allocated_size);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Builds a small tree with a nested directory, a hard-linked file and a symbolic link to
+    /// a file outside the tree, returning its root.
+    #[cfg(unix)]
+    fn du_fixture() -> PathBuf {
+        let root = scratch_dir("du");
+        fs::create_dir(root.join("nested")).unwrap();
+        fs::write(root.join("small"), vec![b'a'; 1000]).unwrap();
+        fs::write(root.join("nested/large"), vec![b'b'; 50_000]).unwrap();
+        fs::hard_link(root.join("nested/large"), root.join("large-link")).unwrap();
+        let outside = root.with_extension("outside");
+        fs::write(&outside, vec![b'c'; 100_000]).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+        root
+    }
+
+    /// Runs `du -s` with `args` on `path`, returning the total in bytes, or `None` if `du`
+    /// is not available.
+    #[cfg(unix)]
+    fn du_total(args: &[&str], path: &Path) -> Option<u64> {
+        let output = process::Command::new("du")
+            .args(args)
+            .arg("-s")
+            .arg(path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let text = String::from_utf8(output.stdout).ok()?;
+        text.split_whitespace().next()?.parse().ok()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn totals_match_du() {
+        let root = du_fixture();
+        let scan = scan(&root, &ScanOptions::default()).unwrap();
+        assert!(scan.errors.is_empty(), "{:?}", scan.errors);
+
+        // root, nested, small, nested/large, large-link and link
+        assert_eq!(scan.root.items, 6);
+
+        // The hard link counts once and the symbolic link as itself, not its target.
+        let entry_size = |path: &Path| fs::symlink_metadata(path).unwrap().len();
+        let expected_size = entry_size(&root)
+            + entry_size(&root.join("nested"))
+            + 1000
+            + 50_000
+            + entry_size(&root.join("link"));
+        assert_eq!(scan.root.size, expected_size);
+        let find = |node: &FileInfo, name: &str| -> u64 {
+            node.children
+                .iter()
+                .flatten()
+                .find(|child| child.path.ends_with(name))
+                .unwrap()
+                .size
+        };
+        let nested = scan
+            .root
+            .children
+            .iter()
+            .flatten()
+            .find(|child| child.path.ends_with("nested"))
+            .unwrap();
+        let mut link_sizes = [find(nested, "large"), find(&scan.root, "large-link")];
+        link_sizes.sort_unstable();
+        assert_eq!(link_sizes, [0, 50_000]);
+
+        if let Some(apparent) = du_total(&["--apparent-size", "-b"], &root) {
+            assert_eq!(scan.root.size, apparent);
+        }
+        if let Some(allocated) = du_total(&["-B1"], &root) {
+            assert_eq!(scan.root.allocated_size, allocated);
+        }
+
+        let totals = scan_totals_with(&root, &ScanOptions::default()).unwrap();
+        assert_eq!(totals.size, scan.root.size);
+        assert_eq!(totals.allocated_size, scan.root.allocated_size);
+        assert_eq!(totals.items, 6);
+        assert_eq!(totals.files, 4);
+
+        fs::remove_file(root.with_extension("outside")).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{json, FileInfo};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Aggregate statistics for a scanned tree, suitable for dashboards and alerting.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub total_size: u64,
+    pub allocated_size: u64,
+    pub items: u64,
+    pub files: u64,
+    pub dirs: u64,
+    pub top_extension: Option<(String, u64)>, // Extension holding the most bytes, and its size
+    pub max_depth: usize,                     // Deepest level below the root; the root is 0
+    pub scan_duration: Option<Duration>,
+}
+
+impl Summary {
+    /// Computes the summary of the tree rooted at `root`.
+    ///
+    /// `scan_duration` is left unset, since only the caller knows how long the scan took.
+    #[must_use]
+    pub fn of(root: &FileInfo) -> Self {
+        let mut summary = Self {
+            total_size: root.size,
+            allocated_size: root.allocated_size,
+            items: root.items,
+            ..Self::default()
+        };
+        let mut extension_sizes = HashMap::new();
+        summary.visit(root, 0, &mut extension_sizes);
+        summary.top_extension = extension_sizes
+            .into_iter()
+            .max_by(|(a_ext, a_size), (b_ext, b_size)| a_size.cmp(b_size).then(b_ext.cmp(a_ext)));
+        summary
+    }
+
+    /// Counts `node` and its descendants, tallying bytes per file extension.
+    fn visit(&mut self, node: &FileInfo, depth: usize, extension_sizes: &mut HashMap<String, u64>) {
+        self.max_depth = self.max_depth.max(depth);
+        if node.is_dir {
+            self.dirs += 1;
+        } else {
+            self.files += 1;
+            if let Some(extension) = node.path.extension() {
+                let extension = extension.to_string_lossy().to_lowercase();
+                *extension_sizes.entry(extension).or_insert(0) += node.size;
+            }
+        }
+        for child in node.children.iter().flatten() {
+            self.visit(child, depth + 1, extension_sizes);
+        }
+    }
+
+    /// Serializes the summary as a single-line JSON object.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let top_extension = self.top_extension.as_ref().map_or_else(
+            || "null".to_string(),
+            |(extension, size)| {
+                format!(
+                    "{{\"extension\":{},\"size\":{size}}}",
+                    json::string(extension)
+                )
+            },
+        );
+        let scan_duration = self.scan_duration.map_or_else(
+            || "null".to_string(),
+            |duration| format!("{:.3}", duration.as_secs_f64()),
+        );
+        format!(
+            "{{\"total_size\":{},\"allocated_size\":{},\"items\":{},\"files\":{},\"dirs\":{},\"top_extension\":{top_extension},\"max_depth\":{},\"scan_duration_secs\":{scan_duration}}}",
+            self.total_size, self.allocated_size, self.items, self.files, self.dirs, self.max_depth
+        )
+    }
+}
+
+/// How a git repository's size splits between its history and its working tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepoSize {
+    pub git_dir: u64,      // Apparent size of the `.git` directory
+    pub working_tree: u64, // Apparent size of everything else
+}
+
+impl RepoSize {
+    /// Splits `root` into its `.git` directory and the rest.
+    ///
+    /// Returns `None` unless `root` has a `.git` directory directly inside it. A `.git`
+    /// file, as used by worktrees and submodules, points elsewhere and does not count.
+    #[must_use]
+    pub fn of(root: &FileInfo) -> Option<Self> {
+        let git_dir = root.children.iter().flatten().find(|child| {
+            child.is_dir && child.path.file_name().is_some_and(|name| name == ".git")
+        })?;
+        Some(Self {
+            git_dir: git_dir.size,
+            working_tree: root.size - git_dir.size,
+        })
+    }
+
+    /// Returns the share of the repository taken by the `.git` directory, in percent.
+    #[must_use]
+    pub fn git_percentage(&self) -> f64 {
+        crate::percentage(self.git_dir, self.git_dir + self.working_tree)
+    }
+}
@@ -0,0 +1,27 @@
+use std::path::Path;
+
+/// Returns the bytes taken by the names and values of the extended attributes of `path`.
+///
+/// A symbolic link is read as the link itself, never its target. Attributes that cannot be
+/// read count as 0, as does everything on filesystems without extended attributes.
+#[cfg(unix)]
+pub fn size_of(path: &Path) -> u64 {
+    let Ok(names) = xattr::list(path) else {
+        return 0;
+    };
+    names
+        .map(|name| {
+            let value = xattr::get(path, &name)
+                .ok()
+                .flatten()
+                .map_or(0, |value| value.len());
+            (name.len() + value) as u64
+        })
+        .sum()
+}
+
+/// Returns 0: extended attributes are not read on this platform.
+#[cfg(not(unix))]
+pub const fn size_of(_path: &Path) -> u64 {
+    0
+}